@@ -0,0 +1,202 @@
+use crate::algorithms::flocking::{FlockParams, Flocking};
+use crate::algorithms::flocking_alpha::{FlockAlphaParams, FlockingAlpha};
+use crate::algorithms::formation_ecbf::{FormationEcbf, FormationEcbfParams};
+use crate::algorithms::gravity::{Gravity, GravityParams};
+use crate::algorithms::registry::{Algorithm, AlgorithmRegistry};
+use crate::algorithms::safe_flocking_alpha::{SafeFlockAlphaParams, SafeFlockingAlpha};
+use crate::engine::{
+    ALGO_FLOCKING, ALGO_FLOCKING_ALPHA, ALGO_FORMATION_ECBF, ALGO_GRAVITY, ALGO_NONE,
+    ALGO_SAFE_FLOCKING_ALPHA, MODEL_FROM_STATES, MODEL_LATTICE, MODEL_QUADROTOR, MODEL_RING,
+};
+use crate::models::particles::ParticleModel;
+use nalgebra::Vector3;
+use std::any::Any;
+
+/// Integrates with zero external forces; the `Algorithm` stand-in for "none".
+#[derive(Debug, Clone, Default)]
+pub struct NoAlgorithm;
+
+impl Algorithm for NoAlgorithm {
+    fn apply(&mut self, _model: &mut ParticleModel, _plane_2d: bool) {}
+
+    fn id(&self) -> &'static str {
+        ALGO_NONE
+    }
+
+    fn name(&self) -> &'static str {
+        "No forces"
+    }
+
+    fn compatible_models(&self) -> &'static [&'static str] {
+        &[MODEL_RING, MODEL_LATTICE, MODEL_FROM_STATES]
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Algorithm for Flocking {
+    fn apply(&mut self, model: &mut ParticleModel, plane_2d: bool) {
+        Flocking::apply(self, model, plane_2d)
+    }
+
+    fn id(&self) -> &'static str {
+        ALGO_FLOCKING
+    }
+
+    fn name(&self) -> &'static str {
+        "Flocking"
+    }
+
+    fn compatible_models(&self) -> &'static [&'static str] {
+        &[MODEL_RING, MODEL_LATTICE, MODEL_FROM_STATES]
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Algorithm for FlockingAlpha {
+    fn apply(&mut self, model: &mut ParticleModel, plane_2d: bool) {
+        FlockingAlpha::apply(self, model, plane_2d)
+    }
+
+    fn id(&self) -> &'static str {
+        ALGO_FLOCKING_ALPHA
+    }
+
+    fn name(&self) -> &'static str {
+        "Flocking alpha-lattice"
+    }
+
+    fn compatible_models(&self) -> &'static [&'static str] {
+        &[MODEL_RING, MODEL_LATTICE, MODEL_FROM_STATES]
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Algorithm for FormationEcbf {
+    fn apply(&mut self, model: &mut ParticleModel, plane_2d: bool) {
+        FormationEcbf::apply(self, model, plane_2d)
+    }
+
+    fn id(&self) -> &'static str {
+        ALGO_FORMATION_ECBF
+    }
+
+    fn name(&self) -> &'static str {
+        "Fixed-time formation + ECBF"
+    }
+
+    fn compatible_models(&self) -> &'static [&'static str] {
+        &[MODEL_RING, MODEL_LATTICE, MODEL_QUADROTOR, MODEL_FROM_STATES]
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn attitudes_flat(&self) -> Vec<f32> {
+        FormationEcbf::attitudes_flat(self)
+    }
+
+    fn reset_agent(&mut self, index: usize, pos: Vector3<f64>, vel: Vector3<f64>) {
+        FormationEcbf::reset_agent(self, index, pos, vel)
+    }
+}
+
+impl Algorithm for SafeFlockingAlpha {
+    fn apply(&mut self, model: &mut ParticleModel, plane_2d: bool) {
+        SafeFlockingAlpha::apply(self, model, plane_2d)
+    }
+
+    fn id(&self) -> &'static str {
+        ALGO_SAFE_FLOCKING_ALPHA
+    }
+
+    fn name(&self) -> &'static str {
+        "Safe flocking (alpha + CBF-QP)"
+    }
+
+    fn compatible_models(&self) -> &'static [&'static str] {
+        &[MODEL_RING, MODEL_LATTICE, MODEL_FROM_STATES]
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn debug_flat(&self) -> Vec<f32> {
+        SafeFlockingAlpha::debug_flat(self)
+    }
+}
+
+impl Algorithm for Gravity {
+    fn apply(&mut self, model: &mut ParticleModel, plane_2d: bool) {
+        Gravity::apply(self, model, plane_2d)
+    }
+
+    fn id(&self) -> &'static str {
+        ALGO_GRAVITY
+    }
+
+    fn name(&self) -> &'static str {
+        "Gravity (Barnes-Hut)"
+    }
+
+    fn compatible_models(&self) -> &'static [&'static str] {
+        &[MODEL_RING, MODEL_LATTICE, MODEL_FROM_STATES]
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Register every built-in controller under its catalog id. Called once,
+/// lazily, from `AlgorithmRegistry::global`.
+pub(crate) fn register_all(registry: &AlgorithmRegistry) {
+    registry.register(ALGO_NONE, || Box::new(NoAlgorithm));
+    registry.register(ALGO_FLOCKING, || {
+        Box::new(Flocking::new(FlockParams::default()))
+    });
+    registry.register(ALGO_FLOCKING_ALPHA, || {
+        Box::new(FlockingAlpha::new(FlockAlphaParams::default()))
+    });
+    registry.register(ALGO_FORMATION_ECBF, || {
+        Box::new(FormationEcbf::new(FormationEcbfParams::default()))
+    });
+    registry.register(ALGO_SAFE_FLOCKING_ALPHA, || {
+        Box::new(SafeFlockingAlpha::new(SafeFlockAlphaParams::default()))
+    });
+    registry.register(ALGO_GRAVITY, || Box::new(Gravity::new(GravityParams::default())));
+}
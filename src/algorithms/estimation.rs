@@ -0,0 +1,299 @@
+use crate::algorithms::rng::Rng64;
+use crate::Point;
+use nalgebra::{SMatrix, SVector, Vector3};
+
+#[derive(Debug, Clone, Copy)]
+struct Hypothesis {
+    pos: Vector3<f64>,
+    vel: Vector3<f64>,
+    weight: f64,
+}
+
+/// Particle-filter state estimator for a single agent whose true position is
+/// only known through noisy measurements, and which may be subject to an
+/// unknown disturbance (e.g. wind). Belief is `P` weighted `(pos, vel)`
+/// hypotheses; the estimate fed to a controller is their weighted mean.
+#[derive(Debug, Clone)]
+pub struct ParticleFilter {
+    particles: Vec<Hypothesis>,
+    wind_std: f64,
+    rng: Rng64,
+}
+
+impl ParticleFilter {
+    pub fn new(pos: Vector3<f64>, vel: Vector3<f64>, count: usize, wind_std: f64, seed: u64) -> Self {
+        let count = count.max(1);
+        let weight = 1.0 / count as f64;
+        Self {
+            particles: vec![Hypothesis { pos, vel, weight }; count],
+            wind_std,
+            rng: Rng64::new(seed),
+        }
+    }
+
+    /// Advance every hypothesis by the commanded acceleration plus an
+    /// independent sampled wind acceleration, then integrate position.
+    pub fn predict(&mut self, dt: f64, control_accel: Vector3<f64>) {
+        for p in self.particles.iter_mut() {
+            let wind = Vector3::new(
+                self.rng.next_gaussian(),
+                self.rng.next_gaussian(),
+                self.rng.next_gaussian(),
+            ) * self.wind_std;
+            p.vel += (control_accel + wind) * dt;
+            p.pos += p.vel * dt;
+        }
+    }
+
+    /// Reweight by the likelihood of `measurement` under each hypothesis,
+    /// then resample early if the effective sample size collapses.
+    pub fn update<L: Fn(Vector3<f64>) -> f64>(&mut self, likelihood: L) {
+        // Snapshot the mean while weights still sum to 1, before the
+        // reweight loop below can collapse them to ~0 (see the degeneracy
+        // branch): mean_position/mean_velocity are weighted sums, so reading
+        // them after a collapse would return ~0 instead of the last belief.
+        let (prior_pos, prior_vel) = (self.mean_position(), self.mean_velocity());
+        let mut total = 0.0;
+        for p in self.particles.iter_mut() {
+            p.weight *= likelihood(p.pos).max(1.0e-12);
+            total += p.weight;
+        }
+        if total <= 1.0e-12 {
+            // Filter degeneracy: every hypothesis collapsed. Reinitialize
+            // around the last good estimate rather than dividing by ~0.
+            self.reinitialize_around(prior_pos, prior_vel);
+            return;
+        }
+        for p in self.particles.iter_mut() {
+            p.weight /= total;
+        }
+        if self.effective_sample_size() < self.particles.len() as f64 * 0.5 {
+            self.resample();
+        }
+    }
+
+    /// Systematic (low-variance) resampling: one uniform draw, then P
+    /// evenly-spaced strides through the cumulative weight array.
+    pub fn resample(&mut self) {
+        let n = self.particles.len();
+        if n == 0 {
+            return;
+        }
+        let mut cumulative = Vec::with_capacity(n);
+        let mut acc = 0.0;
+        for p in &self.particles {
+            acc += p.weight;
+            cumulative.push(acc);
+        }
+        let u0 = self.rng.next_f64() / n as f64;
+        let mut resampled = Vec::with_capacity(n);
+        let mut j = 0;
+        for k in 0..n {
+            let u = u0 + k as f64 / n as f64;
+            while j + 1 < n && cumulative[j] < u {
+                j += 1;
+            }
+            let mut sample = self.particles[j];
+            sample.weight = 1.0 / n as f64;
+            resampled.push(sample);
+        }
+        self.particles = resampled;
+    }
+
+    pub fn effective_sample_size(&self) -> f64 {
+        let sum_sq: f64 = self.particles.iter().map(|p| p.weight * p.weight).sum();
+        if sum_sq > 0.0 {
+            1.0 / sum_sq
+        } else {
+            0.0
+        }
+    }
+
+    pub fn mean_position(&self) -> Vector3<f64> {
+        self.particles
+            .iter()
+            .fold(Vector3::new(0.0, 0.0, 0.0), |acc, p| acc + p.pos * p.weight)
+    }
+
+    pub fn mean_velocity(&self) -> Vector3<f64> {
+        self.particles
+            .iter()
+            .fold(Vector3::new(0.0, 0.0, 0.0), |acc, p| acc + p.vel * p.weight)
+    }
+
+    pub fn reinitialize_around(&mut self, pos: Vector3<f64>, vel: Vector3<f64>) {
+        let n = self.particles.len().max(1) as f64;
+        for p in self.particles.iter_mut() {
+            p.pos = pos;
+            p.vel = vel;
+            p.weight = 1.0 / n;
+        }
+    }
+}
+
+/// Gaussian likelihood of `measurement` given a hypothesis at `pos`, with
+/// isotropic standard deviation `sigma`.
+pub fn gaussian_likelihood(pos: Vector3<f64>, measurement: Vector3<f64>, sigma: f64) -> f64 {
+    let sigma = sigma.max(1.0e-6);
+    let d2 = (pos - measurement).norm_squared();
+    (-0.5 * d2 / (sigma * sigma)).exp()
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Hypothesis6 {
+    state: SVector<f64, 6>,
+    weight: f64,
+}
+
+/// Particle-filter state estimator for a single `Point` whose true state is
+/// only known through noisy measurements and which may be buffeted by wind.
+/// Belief is `P` weighted full `[x, y, z, vx, vy, vz]` hypotheses; `predict`
+/// advances each one with the same RK4 dynamics `Point::step` uses (mass +
+/// linear drag), injecting an independent Gaussian wind acceleration per
+/// hypothesis, so this is the `Point`-shaped counterpart to `ParticleFilter`
+/// above rather than a replacement for it.
+#[derive(Debug, Clone)]
+pub struct PointParticleFilter {
+    mass: f64,
+    drag_coefficient: f64,
+    wind_std: f64,
+    particles: Vec<Hypothesis6>,
+    rng: Rng64,
+}
+
+impl PointParticleFilter {
+    pub fn new(
+        point: &Point,
+        count: usize,
+        wind_std: f64,
+        seed: u64,
+    ) -> Self {
+        let count = count.max(1);
+        let weight = 1.0 / count as f64;
+        let state = SVector::<f64, 6>::from_row_slice(&point.state_array());
+        Self {
+            mass: point.mass().max(1.0e-9),
+            drag_coefficient: point.drag_coefficient(),
+            wind_std,
+            particles: vec![Hypothesis6 { state, weight }; count],
+            rng: Rng64::new(seed),
+        }
+    }
+
+    /// Advance every hypothesis by `Point::step`'s RK4 dynamics, plus an
+    /// independent sampled wind acceleration held fixed across the four
+    /// RK4 sub-stages of that hypothesis's step.
+    pub fn predict(&mut self, dt: f64, control_accel: Vector3<f64>) {
+        let mass = self.mass;
+        let drag = self.drag_coefficient;
+        for p in self.particles.iter_mut() {
+            let wind = Vector3::new(
+                self.rng.next_gaussian(),
+                self.rng.next_gaussian(),
+                self.rng.next_gaussian(),
+            ) * self.wind_std;
+
+            // Like `Point::step`, drag comes from the start-of-step velocity
+            // and the acceleration is held fixed across the four stages.
+            let v = Vector3::new(p.state[3], p.state[4], p.state[5]);
+            let drag_force = -drag * v;
+            let acc = (control_accel + wind) + drag_force / mass;
+            let f = |s: &SVector<f64, 6>| -> SVector<f64, 6> {
+                SVector::<f64, 6>::from_row_slice(&[s[3], s[4], s[5], acc[0], acc[1], acc[2]])
+            };
+
+            let k1 = dt * f(&p.state);
+            let k2 = dt * f(&(p.state + 0.5 * k1));
+            let k3 = dt * f(&(p.state + 0.5 * k2));
+            let k4 = dt * f(&(p.state + k3));
+            p.state += (k1 + 2.0 * k2 + 2.0 * k3 + k4) / 6.0;
+        }
+    }
+
+    /// Reweight by the likelihood of `measurement` under each hypothesis,
+    /// then resample early if the effective sample size collapses.
+    pub fn update<L: Fn(SVector<f64, 6>) -> f64>(&mut self, likelihood: L) {
+        // Snapshot the mean while weights still sum to 1, before the
+        // reweight loop below can collapse them to ~0 (see the degeneracy
+        // branch): mean_state is a weighted sum, so reading it after a
+        // collapse would return ~0 instead of the last belief.
+        let prior_mean = self.mean_state();
+        let mut total = 0.0;
+        for p in self.particles.iter_mut() {
+            p.weight *= likelihood(p.state).max(1.0e-12);
+            total += p.weight;
+        }
+        if total <= 1.0e-12 {
+            // Filter degeneracy: every hypothesis collapsed. Reinitialize
+            // from the prior rather than dividing by ~0.
+            self.reinitialize_around(prior_mean);
+            return;
+        }
+        for p in self.particles.iter_mut() {
+            p.weight /= total;
+        }
+        if self.effective_sample_size() < self.particles.len() as f64 * 0.5 {
+            self.resample();
+        }
+    }
+
+    /// Systematic (low-variance) resampling: one uniform draw, then P
+    /// evenly-spaced strides through the cumulative weight array.
+    pub fn resample(&mut self) {
+        let n = self.particles.len();
+        if n == 0 {
+            return;
+        }
+        let mut cumulative = Vec::with_capacity(n);
+        let mut acc = 0.0;
+        for p in &self.particles {
+            acc += p.weight;
+            cumulative.push(acc);
+        }
+        let u0 = self.rng.next_f64() / n as f64;
+        let mut resampled = Vec::with_capacity(n);
+        let mut j = 0;
+        for k in 0..n {
+            let u = u0 + k as f64 / n as f64;
+            while j + 1 < n && cumulative[j] < u {
+                j += 1;
+            }
+            let mut sample = self.particles[j];
+            sample.weight = 1.0 / n as f64;
+            resampled.push(sample);
+        }
+        self.particles = resampled;
+    }
+
+    pub fn effective_sample_size(&self) -> f64 {
+        let sum_sq: f64 = self.particles.iter().map(|p| p.weight * p.weight).sum();
+        if sum_sq > 0.0 {
+            1.0 / sum_sq
+        } else {
+            0.0
+        }
+    }
+
+    pub fn mean_state(&self) -> SVector<f64, 6> {
+        self.particles
+            .iter()
+            .fold(SVector::<f64, 6>::zeros(), |acc, p| acc + p.state * p.weight)
+    }
+
+    /// Weighted state covariance about `mean_state`.
+    pub fn covariance(&self) -> SMatrix<f64, 6, 6> {
+        let mean = self.mean_state();
+        self.particles.iter().fold(SMatrix::<f64, 6, 6>::zeros(), |acc, p| {
+            let d = p.state - mean;
+            acc + (d * d.transpose()) * p.weight
+        })
+    }
+
+    pub fn reinitialize_around(&mut self, state: SVector<f64, 6>) {
+        let n = self.particles.len().max(1) as f64;
+        for p in self.particles.iter_mut() {
+            p.state = state;
+            p.weight = 1.0 / n;
+        }
+    }
+}
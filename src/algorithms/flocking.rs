@@ -1,5 +1,8 @@
+use crate::algorithms::compute_backend::{build_backend, Backend, FlockForceParams, NeighborSums};
+use crate::algorithms::spatial_grid::SpatialGrid;
 use crate::models::particles::ParticleModel;
 use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
 
 pub const DEFAULT_NEIGHBOR_RADIUS: f64 = 2.6;
 pub const DEFAULT_SEPARATION_RADIUS: f64 = 0.9;
@@ -12,7 +15,7 @@ pub const DEFAULT_MAX_SPEED: f64 = 2.4;
 pub const DEFAULT_MAX_FORCE: f64 = 1.6;
 pub const DEFAULT_SPEED_LIMIT: f64 = 2.0;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlockParams {
     pub neighbor_radius: f64,
     pub separation_radius: f64,
@@ -43,14 +46,123 @@ impl Default for FlockParams {
     }
 }
 
+/// How two groups treat each other for the neighbor-gated rules:
+/// `Cohesion`/`Alignment`/`Separation` only see `Friend` neighbors, and
+/// `FleeEnemies` only sees `Enemy` neighbors. `Neutral` neighbors contribute
+/// to neither.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Relation {
+    Neutral,
+    Friend,
+    Enemy,
+}
+
+/// A point attractor: pulls a body toward `position` proportional to
+/// distance, scaled by `weight`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Goal {
+    pub position: [f64; 3],
+    pub weight: f64,
+}
+
+/// A point/sphere repulsor (a point obstacle is just `radius: 0.0`). Looks
+/// ahead along the body's current velocity instead of reacting only to its
+/// current position, so the body steers clear before it would actually
+/// enter the obstacle.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FlockObstacle {
+    pub center: [f64; 3],
+    pub radius: f64,
+    pub look_ahead: f64,
+    pub weight: f64,
+}
+
+/// One term in a `Flocking`'s rule list. `Cohesion`/`Alignment`/`Separation`
+/// reuse the shared `FlockParams::neighbor_radius`/`separation_radius`
+/// neighbor-sum reduction and only count `Relation::Friend` neighbors (an
+/// empty `Flocking::relations` table makes every pair `Friend`, reproducing
+/// the original group-blind behavior); `FleeEnemies` is the complementary
+/// term, reacting to `Relation::Enemy` neighbors within `separation_radius`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum FlockRule {
+    Cohesion { weight: f64 },
+    Alignment { weight: f64 },
+    Separation { weight: f64 },
+    FleeEnemies { weight: f64 },
+    Boundary { radius: f64, weight: f64 },
+    SpeedLimit { max_speed: f64, weight: f64 },
+    Goal(Goal),
+    Obstacle(FlockObstacle),
+}
+
+/// How a body's registered rule contributions combine into its final force.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RuleCombination {
+    /// Sum every rule's contribution directly (each rule's own `weight`
+    /// already scales it) — the original fixed behavior.
+    #[default]
+    Weighted,
+    /// Apply rules in registration order, clamping each contribution to
+    /// whatever remains of `FlockParams::max_force` and stopping once that
+    /// budget is exhausted.
+    Prioritized,
+    /// Mean of every rule that produced a non-zero contribution.
+    Average,
+}
+
 #[derive(Debug, Clone)]
 pub struct Flocking {
     pub params: FlockParams,
+    /// Which `ComputeBackend` evaluates the shared cohesion/alignment/
+    /// separation neighbor sums. Only consulted while `relations` is empty —
+    /// see `apply`. Defaults to `Backend::Cpu`; set via
+    /// `Engine::set_compute_backend`.
+    pub backend: Backend,
+    /// Ordered rule list; defaults to `Flocking::default_rules(&params)`.
+    pub rules: Vec<FlockRule>,
+    pub combination: RuleCombination,
+    /// Per-group-pair relation overrides. A pair not listed here — including
+    /// a group against itself — defaults to `Relation::Friend`.
+    pub relations: Vec<((usize, usize), Relation)>,
 }
 
 impl Flocking {
     pub fn new(params: FlockParams) -> Self {
-        Self { params }
+        let rules = Self::default_rules(&params);
+        Self {
+            params,
+            backend: Backend::Cpu,
+            rules,
+            combination: RuleCombination::default(),
+            relations: Vec::new(),
+        }
+    }
+
+    /// The rule list reproducing `Flocking`'s original fixed behavior: one
+    /// term each for cohesion, alignment, separation, the spherical
+    /// boundary, and the speed limit, weighted by the matching `FlockParams`
+    /// field.
+    pub fn default_rules(params: &FlockParams) -> Vec<FlockRule> {
+        vec![
+            FlockRule::Cohesion { weight: params.cohesion_weight },
+            FlockRule::Alignment { weight: params.alignment_weight },
+            FlockRule::Separation { weight: params.separation_weight },
+            FlockRule::Boundary { radius: params.boundary_radius, weight: params.boundary_weight },
+            FlockRule::SpeedLimit { max_speed: params.max_speed, weight: params.speed_limit },
+        ]
+    }
+
+    fn relation_of(&self, a: usize, b: usize) -> Relation {
+        if a == b {
+            return Relation::Friend;
+        }
+        self.relations
+            .iter()
+            .find(|((x, y), _)| (*x == a && *y == b) || (*x == b && *y == a))
+            .map(|(_, r)| *r)
+            .unwrap_or(Relation::Friend)
     }
 
     pub fn apply(&self, model: &mut ParticleModel, plane_2d: bool) {
@@ -70,78 +182,222 @@ impl Flocking {
             return;
         }
 
-        let neighbor_r2 = self.params.neighbor_radius * self.params.neighbor_radius;
-        let separation_r2 = self.params.separation_radius * self.params.separation_radius;
-        let mut forces = Vec::with_capacity(n);
+        let needs_flee = self.rules.iter().any(|r| matches!(r, FlockRule::FleeEnemies { .. }));
+
+        let (friend_sums, flee_sums) = if self.relations.is_empty() {
+            let backend = build_backend(self.backend);
+            let sums = backend.neighbor_sums(
+                &positions,
+                &velocities,
+                &FlockForceParams {
+                    neighbor_radius: self.params.neighbor_radius,
+                    separation_radius: self.params.separation_radius,
+                },
+            );
+            (sums, vec![NeighborSums::default(); n])
+        } else {
+            // Per-neighbor relation checks don't fit the backend's
+            // NeighborSums reduction, so this path walks a SpatialGrid
+            // directly instead of going through `self.backend` — plain CPU,
+            // not GPU-accelerated, only taken once relations are configured.
+            let groups = model.groups().to_vec();
+            self.relation_aware_sums(&positions, &velocities, &groups, needs_flee)
+        };
 
+        let mut forces = Vec::with_capacity(n);
         for i in 0..n {
             let pos_i = positions[i];
             let vel_i = velocities[i];
-            let mut cohesion_sum = Vector3::new(0.0, 0.0, 0.0);
-            let mut alignment_sum = Vector3::new(0.0, 0.0, 0.0);
-            let mut separation_sum = Vector3::new(0.0, 0.0, 0.0);
-            let mut neighbors = 0usize;
-            let mut close = 0usize;
-
-            for j in 0..n {
-                if i == j {
-                    continue;
+
+            let contributions: Vec<Vector3<f64>> = self
+                .rules
+                .iter()
+                .map(|rule| self.rule_force(rule, pos_i, vel_i, &friend_sums[i], &flee_sums[i]))
+                .collect();
+
+            let mut force = self.combine(&contributions);
+
+            if !matches!(self.combination, RuleCombination::Prioritized) {
+                let fmag = force.norm();
+                if fmag > self.params.max_force && fmag > 0.0 {
+                    force = force / fmag * self.params.max_force;
                 }
+            }
+
+            if plane_2d {
+                forces.push(Vector3::new(force.x, force.y, 0.0));
+            } else {
+                forces.push(force);
+            }
+        }
+
+        for (i, f) in forces.into_iter().enumerate() {
+            model.set_force(i, f);
+        }
+    }
+
+    /// Grid-restricted neighbor sums split by relation: `Friend` neighbors
+    /// feed the returned `friend` sums (cohesion/alignment within
+    /// `neighbor_radius`, separation within `separation_radius`), `Enemy`
+    /// neighbors feed `flee` (separation-radius repulsion only, skipped
+    /// entirely when no rule needs it), and `Neutral` neighbors contribute
+    /// to neither.
+    fn relation_aware_sums(
+        &self,
+        positions: &[Vector3<f64>],
+        velocities: &[Vector3<f64>],
+        groups: &[usize],
+        needs_flee: bool,
+    ) -> (Vec<NeighborSums>, Vec<NeighborSums>) {
+        let n = positions.len();
+        let neighbor_r2 = self.params.neighbor_radius * self.params.neighbor_radius;
+        let separation_r2 = self.params.separation_radius * self.params.separation_radius;
+        let radius = self.params.neighbor_radius.max(self.params.separation_radius);
+        let grid = SpatialGrid::build(positions, radius.max(1.0e-6));
+
+        let mut friend = vec![NeighborSums::default(); n];
+        let mut flee = vec![NeighborSums::default(); n];
+
+        for i in 0..n {
+            let pos_i = positions[i];
+            for j in grid.neighbors(i, positions, radius) {
+                let relation = self.relation_of(groups[i], groups[j]);
+                let sums = match relation {
+                    Relation::Friend => &mut friend[i],
+                    Relation::Enemy if needs_flee => &mut flee[i],
+                    _ => continue,
+                };
+
                 let diff = pos_i - positions[j];
                 let dist2 = diff.norm_squared();
-                if dist2 < neighbor_r2 {
-                    cohesion_sum += positions[j];
-                    alignment_sum += velocities[j];
-                    neighbors += 1;
+                if relation == Relation::Friend && dist2 < neighbor_r2 {
+                    sums.cohesion_sum += positions[j];
+                    sums.alignment_sum += velocities[j];
+                    sums.neighbors += 1;
                 }
                 if dist2 < separation_r2 && dist2 > 1.0e-12 {
                     let dist = dist2.sqrt();
-                    separation_sum += diff / dist;
-                    close += 1;
+                    sums.separation_sum += diff / dist;
+                    sums.close += 1;
                 }
             }
+        }
 
-            let mut force = Vector3::new(0.0, 0.0, 0.0);
+        (friend, flee)
+    }
 
-            if neighbors > 0 {
-                let inv = 1.0 / neighbors as f64;
-                let avg_pos = cohesion_sum * inv;
-                let avg_vel = alignment_sum * inv;
-                force += (avg_pos - pos_i) * self.params.cohesion_weight;
-                force += (avg_vel - vel_i) * self.params.alignment_weight;
+    fn rule_force(
+        &self,
+        rule: &FlockRule,
+        pos_i: Vector3<f64>,
+        vel_i: Vector3<f64>,
+        friend: &NeighborSums,
+        flee: &NeighborSums,
+    ) -> Vector3<f64> {
+        match rule {
+            FlockRule::Cohesion { weight } => {
+                if friend.neighbors > 0 {
+                    let avg_pos = friend.cohesion_sum / friend.neighbors as f64;
+                    (avg_pos - pos_i) * *weight
+                } else {
+                    Vector3::zeros()
+                }
             }
-
-            if close > 0 {
-                let inv = 1.0 / close as f64;
-                force += separation_sum * inv * self.params.separation_weight;
+            FlockRule::Alignment { weight } => {
+                if friend.neighbors > 0 {
+                    let avg_vel = friend.alignment_sum / friend.neighbors as f64;
+                    (avg_vel - vel_i) * *weight
+                } else {
+                    Vector3::zeros()
+                }
             }
-
-            let dist = pos_i.norm();
-            if dist > self.params.boundary_radius && dist > 0.0 {
-                let dir = pos_i / dist;
-                force += -dir * (dist - self.params.boundary_radius) * self.params.boundary_weight;
+            FlockRule::Separation { weight } => {
+                if friend.close > 0 {
+                    friend.separation_sum / friend.close as f64 * *weight
+                } else {
+                    Vector3::zeros()
+                }
             }
-
-            let speed = vel_i.norm();
-            if speed > self.params.max_speed && speed > 0.0 {
-                let dir = vel_i / speed;
-                force += -dir * (speed - self.params.max_speed) * self.params.speed_limit;
+            FlockRule::FleeEnemies { weight } => {
+                if flee.close > 0 {
+                    flee.separation_sum / flee.close as f64 * *weight
+                } else {
+                    Vector3::zeros()
+                }
             }
-
-            let fmag = force.norm();
-            if fmag > self.params.max_force && fmag > 0.0 {
-                force = force / fmag * self.params.max_force;
+            FlockRule::Boundary { radius, weight } => {
+                let dist = pos_i.norm();
+                if dist > *radius && dist > 0.0 {
+                    let dir = pos_i / dist;
+                    -dir * (dist - radius) * *weight
+                } else {
+                    Vector3::zeros()
+                }
             }
-
-            if plane_2d {
-                forces.push(Vector3::new(force.x, force.y, 0.0));
-            } else {
-                forces.push(force);
+            FlockRule::SpeedLimit { max_speed, weight } => {
+                let speed = vel_i.norm();
+                if speed > *max_speed && speed > 0.0 {
+                    let dir = vel_i / speed;
+                    -dir * (speed - max_speed) * *weight
+                } else {
+                    Vector3::zeros()
+                }
+            }
+            FlockRule::Goal(goal) => {
+                let target = Vector3::new(goal.position[0], goal.position[1], goal.position[2]);
+                (target - pos_i) * goal.weight
+            }
+            FlockRule::Obstacle(obstacle) => {
+                let center = Vector3::new(obstacle.center[0], obstacle.center[1], obstacle.center[2]);
+                let look_ahead_pos = pos_i + vel_i * obstacle.look_ahead.max(0.0);
+                let diff = look_ahead_pos - center;
+                let dist = diff.norm();
+                if dist < obstacle.radius && dist > 1.0e-9 {
+                    diff / dist * (obstacle.radius - dist) * obstacle.weight
+                } else {
+                    Vector3::zeros()
+                }
             }
         }
+    }
 
-        for (i, f) in forces.into_iter().enumerate() {
-            model.set_force(i, f);
+    fn combine(&self, contributions: &[Vector3<f64>]) -> Vector3<f64> {
+        match self.combination {
+            RuleCombination::Weighted => {
+                contributions.iter().fold(Vector3::zeros(), |acc, c| acc + c)
+            }
+            RuleCombination::Average => {
+                let mut sum = Vector3::zeros();
+                let mut count = 0usize;
+                for c in contributions {
+                    if c.norm_squared() > 0.0 {
+                        sum += c;
+                        count += 1;
+                    }
+                }
+                if count > 0 {
+                    sum / count as f64
+                } else {
+                    sum
+                }
+            }
+            RuleCombination::Prioritized => {
+                let mut remaining = self.params.max_force.max(0.0);
+                let mut total = Vector3::zeros();
+                for c in contributions {
+                    if remaining <= 0.0 {
+                        break;
+                    }
+                    let mag = c.norm();
+                    if mag <= 0.0 {
+                        continue;
+                    }
+                    let take = mag.min(remaining);
+                    total += c / mag * take;
+                    remaining -= take;
+                }
+                total
+            }
         }
     }
 }
@@ -2,6 +2,7 @@ use crate::algorithms::flocking::{
     DEFAULT_BOUNDARY_RADIUS, DEFAULT_BOUNDARY_WEIGHT, DEFAULT_MAX_FORCE, DEFAULT_MAX_SPEED,
     DEFAULT_SPEED_LIMIT,
 };
+use crate::algorithms::spatial_grid::SpatialGrid;
 use crate::models::particles::ParticleModel;
 use nalgebra::Vector3;
 use serde::{Deserialize, Serialize};
@@ -14,6 +15,17 @@ pub const DEFAULT_ALPHA_PHI_A: f64 = 5.0;
 pub const DEFAULT_ALPHA_PHI_B: f64 = 5.0;
 pub const DEFAULT_ALPHA_WEIGHT: f64 = 1.0;
 pub const DEFAULT_ALPHA_ALIGNMENT_WEIGHT: f64 = 0.65;
+pub const DEFAULT_ALPHA_C1_BETA: f64 = 1.0;
+pub const DEFAULT_ALPHA_C2_BETA: f64 = 1.0;
+pub const DEFAULT_ALPHA_BETA_RADIUS: f64 = 2.0;
+
+/// Static collision geometry for beta-agent obstacle avoidance.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Obstacle {
+    Sphere { center: [f64; 3], radius: f64 },
+    HalfPlane { point: [f64; 3], normal: [f64; 3] },
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -44,6 +56,27 @@ pub struct FlockAlphaParams {
     pub max_speed: f64,
     pub max_force: f64,
     pub speed_limit: f64,
+
+    /// Optional rendezvous target (position, velocity) for the gamma-agent
+    /// navigational feedback term. When set, each particle is additionally
+    /// pulled toward `target_position` with a `sigma1`-bounded position term
+    /// (so distant agents are not over-accelerated) plus a velocity-matching
+    /// term toward `target_velocity`.
+    pub target_position: Option<[f64; 3]>,
+    pub target_velocity: [f64; 3],
+    pub c1_gamma: f64,
+    pub c2_gamma: f64,
+
+    /// Static collision geometry (spheres and half-planes) the beta-agent
+    /// term steers each particle around.
+    pub obstacles: Vec<Obstacle>,
+    /// Interaction range for obstacles (m); beta-agents farther than this
+    /// from a particle do not contribute.
+    pub beta_radius: f64,
+    /// Desired clearance distance d_beta (m) from an obstacle surface.
+    pub beta_desired_distance: f64,
+    pub c1_beta: f64,
+    pub c2_beta: f64,
 }
 
 impl Default for FlockAlphaParams {
@@ -62,6 +95,15 @@ impl Default for FlockAlphaParams {
             max_speed: DEFAULT_MAX_SPEED,
             max_force: DEFAULT_MAX_FORCE,
             speed_limit: DEFAULT_SPEED_LIMIT,
+            target_position: None,
+            target_velocity: [0.0, 0.0, 0.0],
+            c1_gamma: 1.0,
+            c2_gamma: 1.0,
+            obstacles: Vec::new(),
+            beta_radius: DEFAULT_ALPHA_BETA_RADIUS,
+            beta_desired_distance: DEFAULT_ALPHA_DESIRED_DISTANCE,
+            c1_beta: DEFAULT_ALPHA_C1_BETA,
+            c2_beta: DEFAULT_ALPHA_C2_BETA,
         }
     }
 }
@@ -134,6 +176,18 @@ impl FlockingAlpha {
         let d_alpha = sigma_norm_scalar(d.max(0.0), eps);
         let r_alpha = sigma_norm_scalar(r, eps);
 
+        let mut beta_radius = self.params.beta_radius;
+        if !beta_radius.is_finite() || beta_radius <= 0.0 {
+            beta_radius = DEFAULT_ALPHA_BETA_RADIUS;
+        }
+        let beta_radius2 = beta_radius * beta_radius;
+        let d_beta_sigma = sigma_norm_scalar(self.params.beta_desired_distance.max(0.0), eps);
+
+        // Bin each particle into a cell of side `r`: neighbor queries then
+        // only visit the cell ring around `i` instead of scanning every
+        // other particle, cutting this from O(n^2) to roughly O(n).
+        let grid = SpatialGrid::build(&positions, r);
+
         let mut forces = Vec::with_capacity(n);
         for i in 0..n {
             let pos_i = positions[i];
@@ -143,10 +197,7 @@ impl FlockingAlpha {
             let mut cons_sum = Vector3::new(0.0, 0.0, 0.0);
             let mut neighbors = 0usize;
 
-            for j in 0..n {
-                if i == j {
-                    continue;
-                }
+            for j in grid.neighbors(i, &positions, r) {
                 let diff = positions[j] - pos_i; // q_j - q_i
                 let dist2 = diff.norm_squared();
                 if dist2 > neighbor_r2 {
@@ -171,6 +222,38 @@ impl FlockingAlpha {
 
             let mut force = grad_sum * self.params.alpha_weight + cons_sum * self.params.alignment_weight;
 
+            // Gamma-agent navigational feedback: pull toward a rendezvous
+            // target with a sigma1-bounded position term (so distant agents
+            // are not over-accelerated) plus velocity matching.
+            if let Some(target_position) = self.params.target_position {
+                let q_gamma = vec3_from(target_position);
+                let p_gamma = vec3_from(self.params.target_velocity);
+                force += sigma1_vec(pos_i - q_gamma) * -self.params.c1_gamma
+                    + (vel_i - p_gamma) * -self.params.c2_gamma;
+            }
+
+            // Beta-agent obstacle avoidance: project onto each nearby
+            // obstacle's surface and add a purely repulsive term plus a
+            // tangential-velocity-matching term, both gated by the bump
+            // function so far-away obstacles contribute nothing.
+            for obstacle in &self.params.obstacles {
+                let (q_hat, p_hat) = project_beta(obstacle, pos_i, vel_i);
+                let diff = q_hat - pos_i;
+                let dist2 = diff.norm_squared();
+                if dist2 > beta_radius2 {
+                    continue;
+                }
+
+                let denom = (1.0 + eps * dist2).sqrt();
+                let z = (denom - 1.0) / eps; // ||q_hat - q_i||_sigma
+                let n_hat = if denom > 0.0 { diff / denom } else { diff };
+
+                let bik = bump_rho(z / d_beta_sigma.max(1.0e-9), h);
+                let phi_beta = bik * (sigma1(z - d_beta_sigma) - 1.0);
+                force += n_hat * phi_beta * self.params.c1_beta;
+                force += (p_hat - vel_i) * bik * self.params.c2_beta;
+            }
+
             // Soft boundary centered at origin.
             let dist = pos_i.norm();
             if dist > self.params.boundary_radius && dist > 0.0 {
@@ -203,6 +286,44 @@ impl FlockingAlpha {
     }
 }
 
+/// Closest point on `obstacle`'s surface to `pos` (`q_hat`) and the
+/// tangential component of `vel` at that point (`p_hat`).
+fn project_beta(obstacle: &Obstacle, pos: Vector3<f64>, vel: Vector3<f64>) -> (Vector3<f64>, Vector3<f64>) {
+    let n_surf = match *obstacle {
+        Obstacle::Sphere { center, radius: _ } => {
+            let center = vec3_from(center);
+            let rd = pos - center;
+            let dist = rd.norm();
+            if dist > 1.0e-9 {
+                rd / dist
+            } else {
+                Vector3::new(1.0, 0.0, 0.0)
+            }
+        }
+        Obstacle::HalfPlane { normal, .. } => {
+            let normal = vec3_from(normal);
+            let len = normal.norm();
+            if len > 1.0e-9 {
+                normal / len
+            } else {
+                Vector3::new(0.0, 0.0, 1.0)
+            }
+        }
+    };
+
+    let q_hat = match *obstacle {
+        Obstacle::Sphere { center, radius } => vec3_from(center) + n_surf * radius,
+        Obstacle::HalfPlane { point, .. } => {
+            let point = vec3_from(point);
+            let signed_dist = (pos - point).dot(&n_surf);
+            pos - n_surf * signed_dist
+        }
+    };
+
+    let p_hat = vel - n_surf * vel.dot(&n_surf);
+    (q_hat, p_hat)
+}
+
 fn sigma_norm_scalar(value: f64, eps: f64) -> f64 {
     ((1.0 + eps * value * value).sqrt() - 1.0) / eps
 }
@@ -228,6 +349,14 @@ fn sigma1(x: f64) -> f64 {
     x / (1.0 + x * x).sqrt()
 }
 
+fn sigma1_vec(x: Vector3<f64>) -> Vector3<f64> {
+    x / (1.0 + x.norm_squared()).sqrt()
+}
+
+fn vec3_from(v: [f64; 3]) -> Vector3<f64> {
+    Vector3::new(v[0], v[1], v[2])
+}
+
 fn uneven_phi(x: f64, a: f64, b: f64) -> f64 {
     let denom = (4.0 * a * b).sqrt();
     let c = if denom > 0.0 { (a - b).abs() / denom } else { 0.0 };
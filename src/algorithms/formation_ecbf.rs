@@ -15,6 +15,11 @@ pub enum LeaderTrajectory {
     Poly { a2: [f64; 3], a1: [f64; 3], a0: [f64; 3] },
     Circle { center: [f64; 3], radius: f64, omega: f64 },
     Custom,
+    /// Piecewise leader route through `points`, arc-length parameterized at
+    /// `speed` (m/s) and Catmull-Rom blended for a C1 path (falls back to
+    /// linear segments under 4 points). `loop_path` wraps back to the start
+    /// instead of stopping at the last waypoint.
+    Waypoints { points: Vec<[f64; 3]>, speed: f64, loop_path: bool },
 }
 
 impl LeaderTrajectory {
@@ -63,6 +68,9 @@ impl LeaderTrajectory {
                 (p, v, a)
             }
             LeaderTrajectory::Custom => custom_leader_trajectory(t),
+            LeaderTrajectory::Waypoints { points, speed, loop_path } => {
+                waypoints_state(points, *speed, *loop_path, t)
+            }
         }
     }
 }
@@ -83,8 +91,126 @@ fn custom_leader_trajectory(t: f64) -> (Vector3<f64>, Vector3<f64>, Vector3<f64>
     (p, v, a)
 }
 
+/// `(p, v, a)` for `LeaderTrajectory::Waypoints` at time `t`: maps arc length
+/// `s = speed * t` (wrapped mod total length when `loop_path`, clamped to
+/// `[0, total length]` otherwise) to a segment and local fraction `u`, then
+/// evaluates a Catmull-Rom spline through the four surrounding control
+/// points (falling back to a straight segment, zero acceleration, when there
+/// are fewer than 4 waypoints). Degenerates to holding position `points[0]`
+/// with zero velocity/acceleration when there are 0-1 points or the path has
+/// ~zero total length.
+fn waypoints_state(
+    points: &[[f64; 3]],
+    speed: f64,
+    loop_path: bool,
+    t: f64,
+) -> (Vector3<f64>, Vector3<f64>, Vector3<f64>) {
+    let pts: Vec<Vector3<f64>> = points.iter().map(|p| Vector3::new(p[0], p[1], p[2])).collect();
+    let n = pts.len();
+    if n == 0 {
+        return (Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 0.0));
+    }
+    if n == 1 {
+        return (pts[0], Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 0.0));
+    }
+
+    let segment_count = if loop_path { n } else { n - 1 };
+    let seg_lens: Vec<f64> = (0..segment_count)
+        .map(|i| (pts[(i + 1) % n] - pts[i]).norm())
+        .collect();
+    let total_len: f64 = seg_lens.iter().sum();
+
+    if total_len < 1.0e-9 {
+        return (pts[0], Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 0.0));
+    }
+
+    let speed = speed.max(0.0);
+    let raw_s = speed * t;
+    let s = if loop_path {
+        raw_s.rem_euclid(total_len)
+    } else {
+        raw_s.clamp(0.0, total_len)
+    };
+
+    let mut seg = segment_count - 1;
+    let mut seg_start = total_len - seg_lens[segment_count - 1];
+    let mut cum = 0.0;
+    for (i, len) in seg_lens.iter().enumerate() {
+        if s < cum + len {
+            seg = i;
+            seg_start = cum;
+            break;
+        }
+        cum += len;
+    }
+    let local_len = seg_lens[seg].max(1.0e-9);
+    let u = ((s - seg_start) / local_len).clamp(0.0, 1.0);
+    let past_end = !loop_path && raw_s >= total_len;
+
+    if n < 4 {
+        let a_pt = pts[seg];
+        let b_pt = pts[(seg + 1) % n];
+        let pos = a_pt + (b_pt - a_pt) * u;
+        if past_end {
+            return (pos, Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 0.0));
+        }
+        let v = (b_pt - a_pt) / local_len * speed;
+        return (pos, v, Vector3::new(0.0, 0.0, 0.0));
+    }
+
+    let idx = |k: i64| -> usize {
+        if loop_path {
+            k.rem_euclid(n as i64) as usize
+        } else {
+            k.clamp(0, n as i64 - 1) as usize
+        }
+    };
+    let p0 = pts[idx(seg as i64 - 1)];
+    let p1 = pts[idx(seg as i64)];
+    let p2 = pts[idx(seg as i64 + 1)];
+    let p3 = pts[idx(seg as i64 + 2)];
+
+    let pos = (p1 * 2.0
+        + (p2 - p0) * u
+        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * (u * u)
+        + (p3 - p0 + (p1 - p2) * 3.0) * (u * u * u))
+        * 0.5;
+
+    if past_end {
+        return (pos, Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 0.0));
+    }
+
+    let dpos_du = ((p2 - p0)
+        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * (2.0 * u)
+        + (p3 - p0 + (p1 - p2) * 3.0) * (3.0 * u * u))
+        * 0.5;
+    let d2pos_du2 = ((p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * 2.0 + (p3 - p0 + (p1 - p2) * 3.0) * (6.0 * u)) * 0.5;
+
+    let du_ds = 1.0 / local_len;
+    let v = dpos_du * (du_ds * speed);
+    let a = d2pos_du2 * (du_ds * du_ds * speed * speed);
+    (pos, v, a)
+}
+
 // ObstaclePoly lives in algorithms/obstacles.rs
 
+/// Fixed-step scheme for the distributed/disturbance observer ODEs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IntegrationScheme {
+    /// Forward Euler: `x += f(x) * dt`. Cheapest, least accurate at large `dt`.
+    Euler,
+    /// Classical 4th-order Runge-Kutta: four evaluations of `f` per step,
+    /// `x += dt/6 * (k1 + 2k2 + 2k3 + k4)`.
+    Rk4,
+}
+
+impl Default for IntegrationScheme {
+    fn default() -> Self {
+        IntegrationScheme::Euler
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct FormationEcbfParams {
@@ -151,6 +277,14 @@ pub struct FormationEcbfParams {
 
     // Moving obstacle terms in L_f h
     pub use_moving_obstacle_terms: bool,
+
+    // Observer step scheme
+    pub integration_scheme: IntegrationScheme,
+
+    // Leader reference governor
+    pub use_reference_governor: bool,
+    pub governor_decay_pos: f64,
+    pub governor_decay_vel: f64,
 }
 
 impl Default for FormationEcbfParams {
@@ -212,6 +346,10 @@ impl Default for FormationEcbfParams {
                 omega: 0.2,
             },
             use_moving_obstacle_terms: true,
+            integration_scheme: IntegrationScheme::Euler,
+            use_reference_governor: false,
+            governor_decay_pos: -0.3,
+            governor_decay_vel: -1.2,
         }
     }
 }
@@ -240,6 +378,7 @@ struct FormationEcbfState {
     attitudes: Vec<Vector3<f64>>,
     thrusts: Vec<f64>,
     leader_hold: Option<(Vector3<f64>, Vector3<f64>, Vector3<f64>)>,
+    governor_tracked: Option<(Vector3<f64>, Vector3<f64>)>,
 }
 
 impl FormationEcbf {
@@ -272,7 +411,8 @@ impl FormationEcbf {
             }
         }
 
-        let (p0, v0, _a0) = self.leader_state(t);
+        let (raw_p0, raw_v0, _a0) = self.leader_state(t);
+        let (p0, v0) = self.governed_leader_state(raw_p0, raw_v0);
         self.ensure_state(n, &positions, &velocities, p0, v0);
         self.ensure_offsets(&positions, p0);
         self.update_distributed_observer(n, dt, p0, v0);
@@ -355,6 +495,7 @@ impl FormationEcbf {
         self.state.attitudes = vec![Vector3::new(0.0, 0.0, 0.0); n];
         self.state.thrusts = vec![0.0; n];
         self.state.leader_hold = None;
+        self.state.governor_tracked = None;
     }
 
     fn ensure_offsets(&mut self, positions: &[Vector3<f64>], leader_pos: Vector3<f64>) {
@@ -384,10 +525,23 @@ impl FormationEcbf {
         self.state.offsets_ready = true;
     }
 
-    fn update_distributed_observer(&mut self, n: usize, dt: f64, p0: Vector3<f64>, v0: Vector3<f64>) {
+    /// `(chi_dot, varsigma_dot)` for every agent given a candidate
+    /// `(chi, varsigma)` state — pure in the RK4 sense: re-evaluating it at
+    /// an intermediate state only requires that candidate state, plus the
+    /// leader reference `(p0, v0)` and adjacency, both fixed for the step.
+    fn distributed_observer_derivative(
+        &self,
+        chi: &[Vector3<f64>],
+        varsigma: &[Vector3<f64>],
+        n: usize,
+        p0: Vector3<f64>,
+        v0: Vector3<f64>,
+    ) -> (Vec<Vector3<f64>>, Vec<Vector3<f64>>) {
         let a1 = self.params.obs_a1 / self.params.obs_a2.max(1.0e-6);
         let b1 = self.params.obs_b1 / self.params.obs_b2.max(1.0e-6);
         let eps = self.params.smooth_eps;
+        let mut chi_dot = vec![Vector3::new(0.0, 0.0, 0.0); n];
+        let mut varsigma_dot = vec![Vector3::new(0.0, 0.0, 0.0); n];
         for i in 0..n {
             let mut sum_chi = Vector3::new(0.0, 0.0, 0.0);
             let mut sum_var = Vector3::new(0.0, 0.0, 0.0);
@@ -399,25 +553,65 @@ impl FormationEcbf {
                 if aij == 0.0 {
                     continue;
                 }
-                sum_chi += (self.state.chi[i] - self.state.chi[j]) * aij;
-                sum_var += (self.state.varsigma[i] - self.state.varsigma[j]) * aij;
+                sum_chi += (chi[i] - chi[j]) * aij;
+                sum_var += (varsigma[i] - varsigma[j]) * aij;
             }
             let a_i0 = self.leader_link(i, n);
             if a_i0 != 0.0 {
-                sum_chi += (self.state.chi[i] - p0) * a_i0;
-                sum_var += (self.state.varsigma[i] - v0) * a_i0;
+                sum_chi += (chi[i] - p0) * a_i0;
+                sum_var += (varsigma[i] - v0) * a_i0;
             }
 
-            let chi_dot = -self.params.obs_k1 * sig_pow_vec(sum_chi, a1, eps)
+            chi_dot[i] = -self.params.obs_k1 * sig_pow_vec(sum_chi, a1, eps)
                 - self.params.obs_k2 * sig_pow_vec(sum_chi, b1, eps)
-                + self.state.varsigma[i];
-            let varsigma_dot = -self.params.obs_k1 * sig_pow_vec(sum_var, a1, eps)
+                + varsigma[i];
+            varsigma_dot[i] = -self.params.obs_k1 * sig_pow_vec(sum_var, a1, eps)
                 - self.params.obs_k2 * sig_pow_vec(sum_var, b1, eps)
                 - self.params.obs_k3 * sign_vec(sum_var, eps);
-
-            self.state.chi[i] += chi_dot * dt;
-            self.state.varsigma[i] += varsigma_dot * dt;
         }
+        (chi_dot, varsigma_dot)
+    }
+
+    fn update_distributed_observer(&mut self, n: usize, dt: f64, p0: Vector3<f64>, v0: Vector3<f64>) {
+        let chi0 = self.state.chi.clone();
+        let var0 = self.state.varsigma.clone();
+
+        let (chi1, var1) = match self.params.integration_scheme {
+            IntegrationScheme::Euler => {
+                let (chi_dot, var_dot) = self.distributed_observer_derivative(&chi0, &var0, n, p0, v0);
+                let chi1 = (0..n).map(|i| chi0[i] + chi_dot[i] * dt).collect();
+                let var1 = (0..n).map(|i| var0[i] + var_dot[i] * dt).collect();
+                (chi1, var1)
+            }
+            IntegrationScheme::Rk4 => {
+                let half = dt * 0.5;
+                let k1 = self.distributed_observer_derivative(&chi0, &var0, n, p0, v0);
+
+                let chi_s2: Vec<_> = (0..n).map(|i| chi0[i] + k1.0[i] * half).collect();
+                let var_s2: Vec<_> = (0..n).map(|i| var0[i] + k1.1[i] * half).collect();
+                let k2 = self.distributed_observer_derivative(&chi_s2, &var_s2, n, p0, v0);
+
+                let chi_s3: Vec<_> = (0..n).map(|i| chi0[i] + k2.0[i] * half).collect();
+                let var_s3: Vec<_> = (0..n).map(|i| var0[i] + k2.1[i] * half).collect();
+                let k3 = self.distributed_observer_derivative(&chi_s3, &var_s3, n, p0, v0);
+
+                let chi_s4: Vec<_> = (0..n).map(|i| chi0[i] + k3.0[i] * dt).collect();
+                let var_s4: Vec<_> = (0..n).map(|i| var0[i] + k3.1[i] * dt).collect();
+                let k4 = self.distributed_observer_derivative(&chi_s4, &var_s4, n, p0, v0);
+
+                let sixth = dt / 6.0;
+                let chi1 = (0..n)
+                    .map(|i| chi0[i] + (k1.0[i] + k2.0[i] * 2.0 + k3.0[i] * 2.0 + k4.0[i]) * sixth)
+                    .collect();
+                let var1 = (0..n)
+                    .map(|i| var0[i] + (k1.1[i] + k2.1[i] * 2.0 + k3.1[i] * 2.0 + k4.1[i]) * sixth)
+                    .collect();
+                (chi1, var1)
+            }
+        };
+
+        self.state.chi = chi1;
+        self.state.varsigma = var1;
     }
 
     fn compute_alpha(&self, z1: Vector3<f64>, varsigma: Vector3<f64>) -> Vector3<f64> {
@@ -442,6 +636,33 @@ impl FormationEcbf {
         filtered
     }
 
+    /// `(v_hat_dot, p_dot, theta_dot)` for a candidate `(v_hat, p_state,
+    /// theta_hat)` state. `mu_dot` is the filtered disturbance-velocity
+    /// derivative estimate for this step — it's a discrete measurement, not
+    /// a function of the candidate state, so it (like `u`/`gamma_v`) is held
+    /// fixed across RK4 sub-stages rather than recomputed at each one.
+    fn disturbance_derivative(
+        params: &FormationEcbfParams,
+        _v_hat: Vector3<f64>,
+        p_state: Vector3<f64>,
+        theta_hat: Vector3<f64>,
+        u: Vector3<f64>,
+        gamma_v: Vector3<f64>,
+        mu_dot: Vector3<f64>,
+    ) -> (Vector3<f64>, Vector3<f64>, Vector3<f64>) {
+        let v_hat_dot = u + gamma_v + theta_hat;
+
+        let s = mu_dot + params.do_kappa1 * p_state;
+        let p_dot = params.do_kappa2 * sig_pow_vec(mu_dot, params.do_n1, params.smooth_eps)
+            + params.do_kappa3 * sig_pow_vec(mu_dot, params.do_n2, params.smooth_eps);
+        let theta_dot = params.do_kappa1 * p_dot
+            + params.do_eta1 * sig_pow_vec(s, params.do_n1, params.smooth_eps)
+            + params.do_eta2 * sig_pow_vec(s, params.do_n2, params.smooth_eps)
+            + params.do_eta3 * sign_vec(s, params.smooth_eps);
+
+        (v_hat_dot, p_dot, theta_dot)
+    }
+
     fn update_disturbance_observer(
         &mut self,
         i: usize,
@@ -450,9 +671,22 @@ impl FormationEcbf {
         gamma_v: Vector3<f64>,
         dt: f64,
     ) {
-        let v_hat_dot = u + gamma_v + self.state.theta_hat[i];
-        self.state.v_hat[i] += v_hat_dot * dt;
-        let mu = v - self.state.v_hat[i];
+        // Euler matches the pre-RK4 baseline, which advanced v_hat by its
+        // own (mu-independent) derivative before measuring mu against the
+        // now-current value; Rk4 is a new scheme with no baseline to match,
+        // so it measures mu against the start-of-step v_hat like every other
+        // candidate state it evaluates.
+        let mu = match self.params.integration_scheme {
+            IntegrationScheme::Euler => {
+                let v_hat_dot = u + gamma_v + self.state.theta_hat[i];
+                v - (self.state.v_hat[i] + v_hat_dot * dt)
+            }
+            IntegrationScheme::Rk4 => v - self.state.v_hat[i],
+        };
+        if !self.state.mu_ready[i] && self.params.integration_scheme == IntegrationScheme::Euler {
+            // The baseline advanced v_hat on the priming call too.
+            self.state.v_hat[i] += (u + gamma_v + self.state.theta_hat[i]) * dt;
+        }
         if !self.state.mu_ready[i] {
             self.state.mu_prev[i] = mu;
             self.state.mu_dot_prev[i] = Vector3::new(0.0, 0.0, 0.0);
@@ -464,17 +698,58 @@ impl FormationEcbf {
         let beta = self.params.mu_dot_filter.clamp(0.0, 0.999);
         let mu_dot = self.state.mu_dot_prev[i] * beta + raw_mu_dot * (1.0 - beta);
 
-        let s = mu_dot + self.params.do_kappa1 * self.state.p_state[i];
-        let p_dot = self.params.do_kappa2 * sig_pow_vec(mu_dot, self.params.do_n1, self.params.smooth_eps)
-            + self.params.do_kappa3 * sig_pow_vec(mu_dot, self.params.do_n2, self.params.smooth_eps);
-        self.state.p_state[i] += p_dot * dt;
+        let v_hat0 = self.state.v_hat[i];
+        let p0 = self.state.p_state[i];
+        let theta0 = self.state.theta_hat[i];
 
-        let theta_dot = self.params.do_kappa1 * p_dot
-            + self.params.do_eta1 * sig_pow_vec(s, self.params.do_n1, self.params.smooth_eps)
-            + self.params.do_eta2 * sig_pow_vec(s, self.params.do_n2, self.params.smooth_eps)
-            + self.params.do_eta3 * sign_vec(s, self.params.smooth_eps);
-        self.state.theta_hat[i] += theta_dot * dt;
+        let (v_hat1, p1, theta1) = match self.params.integration_scheme {
+            IntegrationScheme::Euler => {
+                let (dv, dp, dth) =
+                    Self::disturbance_derivative(&self.params, v_hat0, p0, theta0, u, gamma_v, mu_dot);
+                (v_hat0 + dv * dt, p0 + dp * dt, theta0 + dth * dt)
+            }
+            IntegrationScheme::Rk4 => {
+                let half = dt * 0.5;
+                let k1 = Self::disturbance_derivative(&self.params, v_hat0, p0, theta0, u, gamma_v, mu_dot);
+                let k2 = Self::disturbance_derivative(
+                    &self.params,
+                    v_hat0 + k1.0 * half,
+                    p0 + k1.1 * half,
+                    theta0 + k1.2 * half,
+                    u,
+                    gamma_v,
+                    mu_dot,
+                );
+                let k3 = Self::disturbance_derivative(
+                    &self.params,
+                    v_hat0 + k2.0 * half,
+                    p0 + k2.1 * half,
+                    theta0 + k2.2 * half,
+                    u,
+                    gamma_v,
+                    mu_dot,
+                );
+                let k4 = Self::disturbance_derivative(
+                    &self.params,
+                    v_hat0 + k3.0 * dt,
+                    p0 + k3.1 * dt,
+                    theta0 + k3.2 * dt,
+                    u,
+                    gamma_v,
+                    mu_dot,
+                );
+                let sixth = dt / 6.0;
+                (
+                    v_hat0 + (k1.0 + k2.0 * 2.0 + k3.0 * 2.0 + k4.0) * sixth,
+                    p0 + (k1.1 + k2.1 * 2.0 + k3.1 * 2.0 + k4.1) * sixth,
+                    theta0 + (k1.2 + k2.2 * 2.0 + k3.2 * 2.0 + k4.2) * sixth,
+                )
+            }
+        };
 
+        self.state.v_hat[i] = v_hat1;
+        self.state.p_state[i] = p1;
+        self.state.theta_hat[i] = theta1;
         self.state.mu_prev[i] = mu;
         self.state.mu_dot_prev[i] = mu_dot;
     }
@@ -507,6 +782,33 @@ impl FormationEcbf {
         self.params.leader.state(scaled)
     }
 
+    /// Smooths the raw leader state into a short exponential-decay horizon,
+    /// `ref(k) = A * exp(B*k) + C` per axis, where `C` is the raw leader
+    /// target, `A` is the offset from the last governed sample, and `B` is
+    /// `governor_decay_pos`/`governor_decay_vel`. Only the first (`k = 1`)
+    /// sample is ever consumed downstream, so that's the only one computed;
+    /// it's fed back as the new tracked state, so a leader-trajectory jump
+    /// relaxes in over several ticks instead of handing the observer an
+    /// instantaneous step. A no-op returning `(p0, v0)` unchanged unless
+    /// `params.use_reference_governor`.
+    fn governed_leader_state(&mut self, p0: Vector3<f64>, v0: Vector3<f64>) -> (Vector3<f64>, Vector3<f64>) {
+        if !self.params.use_reference_governor {
+            self.state.governor_tracked = None;
+            return (p0, v0);
+        }
+
+        let (tracked_p, tracked_v) = self.state.governor_tracked.unwrap_or((p0, v0));
+        let a_p = tracked_p - p0;
+        let a_v = tracked_v - v0;
+        let b_p = self.params.governor_decay_pos;
+        let b_v = self.params.governor_decay_vel;
+
+        let governed_p = a_p * b_p.exp() + p0;
+        let governed_v = a_v * b_v.exp() + v0;
+        self.state.governor_tracked = Some((governed_p, governed_v));
+        (governed_p, governed_v)
+    }
+
     fn build_constraints(
         &self,
         p: Vector3<f64>,
@@ -526,10 +828,15 @@ impl FormationEcbf {
             if r2 < 1.0e-10 {
                 continue;
             }
-            let d2 = ob.d * ob.d;
+            // Treat the keep-out radius as inflated by the obstacle's
+            // position-uncertainty bound, so h stays a valid (conservative)
+            // barrier even when the obstacle's true position only matches
+            // `ob.pos(t)` to within `sigma_p`.
+            let d_eff = ob.d + ob.sigma_p.max(0.0);
+            let d2 = d_eff * d_eff;
             let h = r2 - d2;
 
-            let (_v_rel, c_base, lfh) = if self.params.use_moving_obstacle_terms {
+            let (v_rel, c_base, lfh) = if self.params.use_moving_obstacle_terms {
                 let v_ob = ob.vel(t);
                 let a_ob = ob.acc();
                 let v_rel = v - v_ob;
@@ -543,7 +850,17 @@ impl FormationEcbf {
             };
 
             let delta1 = 2.0 * r.norm() * (theta_hat.norm() + self.params.delta_theta);
-            let phi = pi1 * h + pi2 * lfh - delta1;
+            // Worst-case error `L_f h` picks up from not knowing the
+            // obstacle's true velocity (`sigma_v`) or true position
+            // (`sigma_p`, paired with the relative-velocity magnitude as its
+            // curvature contribution to the dot product) exactly.
+            let delta_obstacle = if self.params.use_moving_obstacle_terms {
+                pi2 * 2.0 * r.norm() * ob.sigma_v.max(0.0)
+                    + pi2 * 2.0 * ob.sigma_p.max(0.0) * v_rel.norm()
+            } else {
+                0.0
+            };
+            let phi = pi1 * h + pi2 * lfh - delta1 - delta_obstacle;
 
             let xi1 = 1.0 + self.params.delta2_star;
             let xi2 = 1.0 - self.params.delta2_star;
@@ -562,6 +879,40 @@ impl FormationEcbf {
         out
     }
 
+    /// Per-agent `|p_i - chi_i - offset_i|`, the same error norm the nominal
+    /// control law's `z1` term uses. Exposed for `tuning::evolve_formation_params`'s
+    /// tracking-error fitness term.
+    pub(crate) fn formation_errors(&self, positions: &[Vector3<f64>]) -> Vec<f64> {
+        positions
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let chi = self.state.chi.get(i).copied().unwrap_or(*p);
+                (*p - chi - self.formation_offset(i)).norm()
+            })
+            .collect()
+    }
+
+    /// Minimum `h = |p - obstacle|^2 - (d + sigma_p)^2` over every agent/obstacle pair at
+    /// time `t` (the same barrier value `build_constraints` computes);
+    /// negative means a collision-avoidance violation. `f64::INFINITY` if
+    /// there are no obstacles. Exposed for `tuning::evolve_formation_params`'s
+    /// clearance fitness term.
+    pub(crate) fn min_obstacle_margin(&self, positions: &[Vector3<f64>], t: f64) -> f64 {
+        let mut min_h = f64::INFINITY;
+        for p in positions {
+            for ob in &self.params.obstacles {
+                let r2 = (*p - ob.pos(t)).norm_squared();
+                let d_eff = ob.d + ob.sigma_p.max(0.0);
+                let h = r2 - d_eff * d_eff;
+                if h < min_h {
+                    min_h = h;
+                }
+            }
+        }
+        min_h
+    }
+
     fn formation_offset(&self, i: usize) -> Vector3<f64> {
         if i < self.state.formation_offsets.len() {
             self.state.formation_offsets[i]
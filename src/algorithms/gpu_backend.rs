@@ -0,0 +1,412 @@
+//! `wgpu`-backed `ComputeBackend`: uploads agent positions/velocities as
+//! `f32` `vec4` storage buffers, bins
+//! agents into a uniform grid on the CPU (an open-addressing hash table
+//! keyed by `floor(pos / cell_size)`, cheap next to the O(N^2) pairwise
+//! scan it replaces), and dispatches one shader invocation per agent that
+//! walks its 27 neighboring cells — see `flock_forces.wgsl`. Only built
+//! when the `gpu` feature is enabled; `CpuBackend` is the default and needs
+//! no GPU at all.
+
+use crate::algorithms::compute_backend::{ComputeBackend, FlockForceParams, NeighborSums};
+use nalgebra::Vector3;
+use std::sync::OnceLock;
+use wgpu::util::DeviceExt;
+
+const SHADER_SRC: &str = include_str!("flock_forces.wgsl");
+const WORKGROUP_SIZE: u32 = 64;
+// Open-addressing load factor for the cell hash table; matches the probe
+// scheme in `flock_forces.wgsl`'s `find_bucket`.
+const TABLE_LOAD_FACTOR: usize = 2;
+const CELL_COORD_OFFSET: i64 = 1 << 20;
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct GpuParams {
+    neighbor_radius: f32,
+    separation_radius: f32,
+    cell_size: f32,
+    agent_count: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct GpuCellEntry {
+    key_x: u32,
+    key_y: u32,
+    key_z: u32,
+    start: u32,
+    count: u32,
+    _pad: [u32; 3],
+}
+
+const EMPTY_ENTRY: GpuCellEntry = GpuCellEntry {
+    key_x: 0xffff_ffff,
+    key_y: 0xffff_ffff,
+    key_z: 0xffff_ffff,
+    start: 0,
+    count: 0,
+    _pad: [0, 0, 0],
+};
+
+/// Process-wide device/queue/pipeline, built once on first use (mirrors
+/// `AlgorithmRegistry::global`'s `OnceLock` singleton) so constructing a
+/// `GpuBackend` per tick only clones a handful of cheap handles instead of
+/// re-opening the device.
+struct GpuContext {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+}
+
+fn context() -> &'static GpuContext {
+    static CONTEXT: OnceLock<GpuContext> = OnceLock::new();
+    CONTEXT.get_or_init(|| {
+        let instance = wgpu::Instance::default();
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            ..Default::default()
+        }))
+        .expect("no suitable GPU adapter for the `gpu` compute backend");
+        let (device, queue) = pollster::block_on(
+            adapter.request_device(&wgpu::DeviceDescriptor::default(), None),
+        )
+        .expect("failed to open a GPU device for the `gpu` compute backend");
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("flock_forces"),
+            source: wgpu::ShaderSource::Wgsl(SHADER_SRC.into()),
+        });
+
+        let storage = |binding: u32, read_only: bool| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("flock_forces_bgl"),
+            entries: &[
+                storage(0, true),
+                storage(1, true),
+                storage(2, true),
+                storage(3, true),
+                storage(4, false),
+                storage(5, false),
+                storage(6, false),
+                storage(7, false),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 8,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("flock_forces_pl"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("flock_forces_pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "main",
+        });
+
+        GpuContext {
+            device,
+            queue,
+            pipeline,
+            bind_group_layout,
+        }
+    })
+}
+
+/// `ComputeBackend` that evaluates `Flocking`'s neighbor sums on the GPU.
+/// Cheap to construct repeatedly: it only clones handles out of the
+/// process-wide `GpuContext`.
+pub struct GpuBackend {
+    ctx: &'static GpuContext,
+}
+
+impl GpuBackend {
+    pub fn new() -> Self {
+        Self { ctx: context() }
+    }
+}
+
+impl Default for GpuBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Open-addressing cell table + flattened per-cell agent index buckets,
+/// built the same way the shader's `find_bucket` expects to probe them.
+struct CellBinning {
+    table: Vec<GpuCellEntry>,
+    bucket_agents: Vec<u32>,
+}
+
+fn cell_coord(p: Vector3<f64>, cell_size: f64) -> (i64, i64, i64) {
+    (
+        (p.x / cell_size).floor() as i64,
+        (p.y / cell_size).floor() as i64,
+        (p.z / cell_size).floor() as i64,
+    )
+}
+
+fn hash_key(x: u32, y: u32, z: u32, table_len: u32) -> u32 {
+    (x.wrapping_mul(73856093) ^ y.wrapping_mul(19349663) ^ z.wrapping_mul(83492791)) % table_len
+}
+
+fn build_binning(positions: &[Vector3<f64>], cell_size: f64) -> CellBinning {
+    use std::collections::HashMap;
+
+    let n = positions.len();
+    let mut buckets: HashMap<(i64, i64, i64), Vec<u32>> = HashMap::with_capacity(n);
+    for (i, p) in positions.iter().enumerate() {
+        buckets
+            .entry(cell_coord(*p, cell_size))
+            .or_default()
+            .push(i as u32);
+    }
+
+    let table_len = ((buckets.len() * TABLE_LOAD_FACTOR).max(1)).next_power_of_two() as u32;
+    let mut table = vec![EMPTY_ENTRY; table_len as usize];
+    let mut bucket_agents = Vec::with_capacity(n);
+
+    for (coord, agents) in buckets {
+        let key_x = (coord.0 + CELL_COORD_OFFSET) as u32;
+        let key_y = (coord.1 + CELL_COORD_OFFSET) as u32;
+        let key_z = (coord.2 + CELL_COORD_OFFSET) as u32;
+        let mut slot = hash_key(key_x, key_y, key_z, table_len);
+        loop {
+            if table[slot as usize].key_x == 0xffff_ffff {
+                break;
+            }
+            slot = (slot + 1) % table_len;
+        }
+        let start = bucket_agents.len() as u32;
+        let count = agents.len() as u32;
+        bucket_agents.extend_from_slice(&agents);
+        table[slot as usize] = GpuCellEntry {
+            key_x,
+            key_y,
+            key_z,
+            start,
+            count,
+            _pad: [0, 0, 0],
+        };
+    }
+
+    CellBinning {
+        table,
+        bucket_agents,
+    }
+}
+
+fn storage_buffer(device: &wgpu::Device, label: &str, contents: &[u8], usage: wgpu::BufferUsages) -> wgpu::Buffer {
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(label),
+        contents,
+        usage,
+    })
+}
+
+impl ComputeBackend for GpuBackend {
+    fn neighbor_sums(
+        &self,
+        positions: &[Vector3<f64>],
+        velocities: &[Vector3<f64>],
+        params: &FlockForceParams,
+    ) -> Vec<NeighborSums> {
+        let n = positions.len();
+        if n == 0 {
+            return Vec::new();
+        }
+
+        let radius = params.neighbor_radius.max(params.separation_radius);
+        let cell_size = radius.max(1.0e-6);
+        let binning = build_binning(positions, cell_size);
+
+        let pos_buf: Vec<[f32; 4]> = positions
+            .iter()
+            .map(|p| [p.x as f32, p.y as f32, p.z as f32, 0.0])
+            .collect();
+        let vel_buf: Vec<[f32; 4]> = velocities
+            .iter()
+            .map(|v| [v.x as f32, v.y as f32, v.z as f32, 0.0])
+            .collect();
+
+        let device = &self.ctx.device;
+        let queue = &self.ctx.queue;
+
+        let positions_gpu = storage_buffer(
+            device,
+            "flock_positions",
+            bytemuck::cast_slice(&pos_buf),
+            wgpu::BufferUsages::STORAGE,
+        );
+        let velocities_gpu = storage_buffer(
+            device,
+            "flock_velocities",
+            bytemuck::cast_slice(&vel_buf),
+            wgpu::BufferUsages::STORAGE,
+        );
+        let cell_table_gpu = storage_buffer(
+            device,
+            "flock_cell_table",
+            bytemuck::cast_slice(&binning.table),
+            wgpu::BufferUsages::STORAGE,
+        );
+        let bucket_agents_gpu = storage_buffer(
+            device,
+            "flock_bucket_agents",
+            bytemuck::cast_slice(&binning.bucket_agents),
+            wgpu::BufferUsages::STORAGE,
+        );
+
+        let zero_vec4 = vec![[0.0f32; 4]; n];
+        let zero_counts = vec![[0u32; 2]; n];
+        let out_usage = wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC;
+        let out_cohesion = storage_buffer(device, "out_cohesion", bytemuck::cast_slice(&zero_vec4), out_usage);
+        let out_align = storage_buffer(device, "out_align", bytemuck::cast_slice(&zero_vec4), out_usage);
+        let out_sep = storage_buffer(device, "out_sep", bytemuck::cast_slice(&zero_vec4), out_usage);
+        let out_counts = storage_buffer(device, "out_counts", bytemuck::cast_slice(&zero_counts), out_usage);
+
+        let gpu_params = GpuParams {
+            neighbor_radius: params.neighbor_radius as f32,
+            separation_radius: params.separation_radius as f32,
+            cell_size: cell_size as f32,
+            agent_count: n as u32,
+        };
+        let params_gpu = storage_buffer(
+            device,
+            "flock_params",
+            bytemuck::bytes_of(&gpu_params),
+            wgpu::BufferUsages::UNIFORM,
+        );
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("flock_forces_bg"),
+            layout: &self.ctx.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: positions_gpu.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: velocities_gpu.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: cell_table_gpu.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 3, resource: bucket_agents_gpu.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 4, resource: out_cohesion.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 5, resource: out_align.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 6, resource: out_sep.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 7, resource: out_counts.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 8, resource: params_gpu.as_entire_binding() },
+            ],
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("flock_forces_encoder"),
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("flock_forces_pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.ctx.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            let groups = (n as u32).div_ceil(WORKGROUP_SIZE);
+            pass.dispatch_workgroups(groups, 1, 1);
+        }
+
+        let cohesion_read = read_back_buffer(device, &mut encoder, &out_cohesion, n);
+        let align_read = read_back_buffer(device, &mut encoder, &out_align, n);
+        let sep_read = read_back_buffer(device, &mut encoder, &out_sep, n);
+        let counts_read = read_back_buffer(device, &mut encoder, &out_counts, n);
+
+        queue.submit(Some(encoder.finish()));
+
+        let cohesion = map_vec4(device, &cohesion_read);
+        let align = map_vec4(device, &align_read);
+        let sep = map_vec4(device, &sep_read);
+        let counts = map_counts(device, &counts_read);
+
+        (0..n)
+            .map(|i| NeighborSums {
+                cohesion_sum: cohesion[i],
+                alignment_sum: align[i],
+                neighbors: counts[i].0,
+                separation_sum: sep[i],
+                close: counts[i].1,
+            })
+            .collect()
+    }
+}
+
+fn read_back_buffer(
+    device: &wgpu::Device,
+    encoder: &mut wgpu::CommandEncoder,
+    src: &wgpu::Buffer,
+    n: usize,
+) -> wgpu::Buffer {
+    let size = src.size();
+    let staging = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("flock_forces_readback"),
+        size,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    let _ = n;
+    encoder.copy_buffer_to_buffer(src, 0, &staging, 0, size);
+    staging
+}
+
+fn map_vec4(device: &wgpu::Device, buffer: &wgpu::Buffer) -> Vec<Vector3<f64>> {
+    let slice = buffer.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |res| {
+        let _ = sender.send(res);
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver
+        .recv()
+        .expect("GPU buffer map channel closed before completion")
+        .expect("failed to map GPU readback buffer");
+    let data = slice.get_mapped_range();
+    let floats: &[[f32; 4]] = bytemuck::cast_slice(&data);
+    let out = floats
+        .iter()
+        .map(|v| Vector3::new(v[0] as f64, v[1] as f64, v[2] as f64))
+        .collect();
+    drop(data);
+    buffer.unmap();
+    out
+}
+
+fn map_counts(device: &wgpu::Device, buffer: &wgpu::Buffer) -> Vec<(usize, usize)> {
+    let slice = buffer.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |res| {
+        let _ = sender.send(res);
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver
+        .recv()
+        .expect("GPU buffer map channel closed before completion")
+        .expect("failed to map GPU readback buffer");
+    let data = slice.get_mapped_range();
+    let counts: &[[u32; 2]] = bytemuck::cast_slice(&data);
+    let out = counts.iter().map(|c| (c[0] as usize, c[1] as usize)).collect();
+    drop(data);
+    buffer.unmap();
+    out
+}
@@ -0,0 +1,325 @@
+use crate::models::particles::ParticleModel;
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_G: f64 = 1.0;
+pub const DEFAULT_SOFTENING: f64 = 0.05;
+pub const DEFAULT_THETA: f64 = 0.5;
+
+/// Max octree depth before a node just merges further bodies into its mass
+/// instead of recursing — guards against near-coincident positions driving
+/// subdivision to depths where `half_width` underflows to zero.
+const MAX_DEPTH: u32 = 32;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GravityParams {
+    /// Gravitational constant G.
+    pub g: f64,
+    /// Softening length epsilon (m); avoids a singular force as separation -> 0.
+    pub softening: f64,
+    /// Barnes-Hut opening angle theta: a node is treated as a single point
+    /// mass once `region_width / distance < theta`. Smaller is more exact
+    /// and more expensive; a non-positive or non-finite value falls back to
+    /// `DEFAULT_THETA` (set `exact` for the O(N^2) sum).
+    pub theta: f64,
+    /// Skip the Barnes-Hut tree and accumulate exact O(N^2) forces instead
+    /// of the approximation (useful to validate `theta`, or for small N).
+    pub exact: bool,
+}
+
+impl Default for GravityParams {
+    fn default() -> Self {
+        Self {
+            g: DEFAULT_G,
+            softening: DEFAULT_SOFTENING,
+            theta: DEFAULT_THETA,
+            exact: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Gravity {
+    pub params: GravityParams,
+}
+
+impl Gravity {
+    pub fn new(params: GravityParams) -> Self {
+        Self { params }
+    }
+
+    /// Mutual gravitational acceleration `a_i = G * sum_j m_j (x_j - x_i) /
+    /// (|x_j - x_i|^2 + eps^2)^{3/2}`, exact for small `n` or when
+    /// `params.exact` is set, otherwise approximated with a Barnes-Hut
+    /// octree in O(n log n).
+    pub fn apply(&self, model: &mut ParticleModel, plane_2d: bool) {
+        let mut positions = model.positions().to_vec();
+        if plane_2d {
+            for p in positions.iter_mut() {
+                p.z = 0.0;
+            }
+        }
+        let masses = model.masses().to_vec();
+        let n = positions.len();
+        if n == 0 {
+            return;
+        }
+
+        let g = if self.params.g.is_finite() { self.params.g } else { DEFAULT_G };
+        let softening = if self.params.softening.is_finite() && self.params.softening >= 0.0 {
+            self.params.softening
+        } else {
+            DEFAULT_SOFTENING
+        };
+        let eps2 = softening * softening;
+
+        let sums: Vec<Vector3<f64>> = if self.params.exact || n <= 8 {
+            exact_sums(&positions, &masses, eps2)
+        } else {
+            let theta = if self.params.theta.is_finite() && self.params.theta > 0.0 {
+                self.params.theta
+            } else {
+                DEFAULT_THETA
+            };
+            let tree = Octree::build(&positions, &masses);
+            (0..n).map(|i| tree.sum_at(&positions, i, theta, eps2)).collect()
+        };
+
+        for (i, sum) in sums.into_iter().enumerate() {
+            let mut force = sum * (g * masses[i]);
+            if plane_2d {
+                force.z = 0.0;
+            }
+            model.set_force(i, force);
+        }
+    }
+}
+
+/// `sum_j m_j (x_j - x_i) / (|x_j - x_i|^2 + eps2)^{3/2}` for every `i`,
+/// computed directly (O(n^2)).
+fn exact_sums(positions: &[Vector3<f64>], masses: &[f64], eps2: f64) -> Vec<Vector3<f64>> {
+    let n = positions.len();
+    let mut out = Vec::with_capacity(n);
+    for i in 0..n {
+        let mut sum = Vector3::zeros();
+        for j in 0..n {
+            if i == j {
+                continue;
+            }
+            sum += pair_term(positions[j] - positions[i], masses[j], eps2);
+        }
+        out.push(sum);
+    }
+    out
+}
+
+fn pair_term(diff: Vector3<f64>, mass_j: f64, eps2: f64) -> Vector3<f64> {
+    let dist2 = diff.norm_squared() + eps2;
+    diff * (mass_j * dist2.powf(-1.5))
+}
+
+/// Barnes-Hut octree over a fixed set of `positions`/`masses`: each internal
+/// node stores the total mass and center of mass of its subtree, so a query
+/// can treat a whole distant subtree as a single point mass instead of
+/// visiting every body in it.
+struct Octree {
+    root: OctreeNode,
+}
+
+enum OctreeNode {
+    Empty,
+    Leaf {
+        body: usize,
+        mass: f64,
+        center_of_mass: Vector3<f64>,
+    },
+    Internal {
+        mass: f64,
+        center_of_mass: Vector3<f64>,
+        center: Vector3<f64>,
+        half_width: f64,
+        children: Box<[OctreeNode; 8]>,
+    },
+}
+
+impl Octree {
+    fn build(positions: &[Vector3<f64>], masses: &[f64]) -> Self {
+        let mut min = Vector3::repeat(f64::INFINITY);
+        let mut max = Vector3::repeat(f64::NEG_INFINITY);
+        for p in positions {
+            min = min.zip_map(p, f64::min);
+            max = max.zip_map(p, f64::max);
+        }
+        let center = (min + max) * 0.5;
+        let extent = (max - min).amax().max(1.0e-9);
+        let half_width = extent * 0.5 + 1.0e-6;
+
+        let mut root = OctreeNode::Empty;
+        for i in 0..positions.len() {
+            insert(&mut root, i, positions, masses, center, half_width, 0);
+        }
+        Self { root }
+    }
+
+    /// `sum_j m_j (x_j - x_i) / (|x_j - x_i|^2 + eps2)^{3/2}` for body `i`,
+    /// descending the tree and substituting a node's center of mass for its
+    /// whole subtree once `2 * half_width / distance < theta`.
+    fn sum_at(&self, positions: &[Vector3<f64>], i: usize, theta: f64, eps2: f64) -> Vector3<f64> {
+        let mut sum = Vector3::zeros();
+        accumulate(&self.root, positions, i, theta, eps2, &mut sum);
+        sum
+    }
+}
+
+fn insert(
+    node: &mut OctreeNode,
+    body: usize,
+    positions: &[Vector3<f64>],
+    masses: &[f64],
+    center: Vector3<f64>,
+    half_width: f64,
+    depth: u32,
+) {
+    match node {
+        OctreeNode::Empty => {
+            *node = OctreeNode::Leaf {
+                body,
+                mass: masses[body],
+                center_of_mass: positions[body],
+            };
+        }
+        OctreeNode::Leaf { body: existing, .. } if depth >= MAX_DEPTH => {
+            // Too deep to keep subdividing (near-coincident positions);
+            // merge into a combined point mass instead of recursing further.
+            let existing = *existing;
+            let total = masses[existing] + masses[body];
+            let com = (positions[existing] * masses[existing] + positions[body] * masses[body]) / total;
+            *node = OctreeNode::Leaf {
+                body: existing,
+                mass: total,
+                center_of_mass: com,
+            };
+        }
+        OctreeNode::Leaf { body: existing, .. } => {
+            let existing = *existing;
+            let mut children = new_children();
+            let existing_octant = octant_of(center, positions[existing]);
+            let new_octant = octant_of(center, positions[body]);
+            let child_half = half_width * 0.5;
+            insert(
+                &mut children[existing_octant],
+                existing,
+                positions,
+                masses,
+                child_center(center, child_half, existing_octant),
+                child_half,
+                depth + 1,
+            );
+            insert(
+                &mut children[new_octant],
+                body,
+                positions,
+                masses,
+                child_center(center, child_half, new_octant),
+                child_half,
+                depth + 1,
+            );
+            let mass = masses[existing] + masses[body];
+            let center_of_mass =
+                (positions[existing] * masses[existing] + positions[body] * masses[body]) / mass;
+            *node = OctreeNode::Internal {
+                mass,
+                center_of_mass,
+                center,
+                half_width,
+                children,
+            };
+        }
+        OctreeNode::Internal {
+            mass,
+            center_of_mass,
+            center,
+            half_width,
+            children,
+        } => {
+            let new_mass = *mass + masses[body];
+            *center_of_mass = (*center_of_mass * *mass + positions[body] * masses[body]) / new_mass;
+            *mass = new_mass;
+            let child_half = *half_width * 0.5;
+            let oct = octant_of(*center, positions[body]);
+            let child_center = child_center(*center, child_half, oct);
+            insert(&mut children[oct], body, positions, masses, child_center, child_half, depth + 1);
+        }
+    }
+}
+
+fn accumulate(
+    node: &OctreeNode,
+    positions: &[Vector3<f64>],
+    i: usize,
+    theta: f64,
+    eps2: f64,
+    sum: &mut Vector3<f64>,
+) {
+    match node {
+        OctreeNode::Empty => {}
+        OctreeNode::Leaf { body, mass, center_of_mass } => {
+            if *body == i {
+                return;
+            }
+            *sum += pair_term(*center_of_mass - positions[i], *mass, eps2);
+        }
+        OctreeNode::Internal { mass, center_of_mass, half_width, children, .. } => {
+            let diff = *center_of_mass - positions[i];
+            let dist = diff.norm();
+            if dist > 0.0 && (half_width * 2.0) / dist < theta {
+                *sum += pair_term(diff, *mass, eps2);
+                return;
+            }
+            for child in children.iter() {
+                accumulate(child, positions, i, theta, eps2, sum);
+            }
+        }
+    }
+}
+
+fn new_children() -> Box<[OctreeNode; 8]> {
+    Box::new([
+        OctreeNode::Empty,
+        OctreeNode::Empty,
+        OctreeNode::Empty,
+        OctreeNode::Empty,
+        OctreeNode::Empty,
+        OctreeNode::Empty,
+        OctreeNode::Empty,
+        OctreeNode::Empty,
+    ])
+}
+
+/// Which of 8 octants `p` falls in relative to `center`, encoded as
+/// `(x>=center.x) | (y>=center.y)<<1 | (z>=center.z)<<2`.
+fn octant_of(center: Vector3<f64>, p: Vector3<f64>) -> usize {
+    let mut idx = 0;
+    if p.x >= center.x {
+        idx |= 1;
+    }
+    if p.y >= center.y {
+        idx |= 2;
+    }
+    if p.z >= center.z {
+        idx |= 4;
+    }
+    idx
+}
+
+fn child_center(center: Vector3<f64>, child_half: f64, octant: usize) -> Vector3<f64> {
+    let sx = if octant & 1 != 0 { 1.0 } else { -1.0 };
+    let sy = if octant & 2 != 0 { 1.0 } else { -1.0 };
+    let sz = if octant & 4 != 0 { 1.0 } else { -1.0 };
+    Vector3::new(
+        center.x + sx * child_half,
+        center.y + sy * child_half,
+        center.z + sz * child_half,
+    )
+}
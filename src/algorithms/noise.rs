@@ -0,0 +1,119 @@
+use crate::algorithms::rng::Rng64;
+use nalgebra::Vector3;
+
+/// Deterministic 3-D gradient (Perlin-style) noise seeded by `seed`. Not
+/// bit-compatible with any particular library's OpenSimplex/Perlin
+/// implementation — only used where "reproduces exactly for the same seed"
+/// matters more than matching a reference implementation, e.g. procedural
+/// cluster shapes.
+pub fn noise3(p: Vector3<f64>, seed: u64) -> f64 {
+    let perm = permutation_table(seed);
+
+    let xi = p.x.floor();
+    let yi = p.y.floor();
+    let zi = p.z.floor();
+    let xf = p.x - xi;
+    let yf = p.y - yi;
+    let zf = p.z - zi;
+    let (xi, yi, zi) = (xi as i64, yi as i64, zi as i64);
+
+    let u = fade(xf);
+    let v = fade(yf);
+    let w = fade(zf);
+
+    let hash = |x: i64, y: i64, z: i64| -> usize {
+        let ix = x.rem_euclid(256) as usize;
+        let iy = y.rem_euclid(256) as usize;
+        let iz = z.rem_euclid(256) as usize;
+        perm[(perm[(perm[ix] as usize + iy) & 255] as usize + iz) & 255] as usize
+    };
+
+    let aaa = hash(xi, yi, zi);
+    let aba = hash(xi, yi + 1, zi);
+    let aab = hash(xi, yi, zi + 1);
+    let abb = hash(xi, yi + 1, zi + 1);
+    let baa = hash(xi + 1, yi, zi);
+    let bba = hash(xi + 1, yi + 1, zi);
+    let bab = hash(xi + 1, yi, zi + 1);
+    let bbb = hash(xi + 1, yi + 1, zi + 1);
+
+    let x1 = lerp(grad(aaa, xf, yf, zf), grad(baa, xf - 1.0, yf, zf), u);
+    let x2 = lerp(
+        grad(aba, xf, yf - 1.0, zf),
+        grad(bba, xf - 1.0, yf - 1.0, zf),
+        u,
+    );
+    let y1 = lerp(x1, x2, v);
+
+    let x3 = lerp(
+        grad(aab, xf, yf, zf - 1.0),
+        grad(bab, xf - 1.0, yf, zf - 1.0),
+        u,
+    );
+    let x4 = lerp(
+        grad(abb, xf, yf - 1.0, zf - 1.0),
+        grad(bbb, xf - 1.0, yf - 1.0, zf - 1.0),
+        u,
+    );
+    let y2 = lerp(x3, x4, v);
+
+    lerp(y1, y2, w)
+}
+
+/// Fractal sum of `octaves` layers of `noise3` over the same lattice,
+/// multiplying frequency by `lacunarity` and amplitude by `persistence`
+/// from one layer to the next, starting at `freq=1, amp=1`.
+pub fn layered_noise3(p: Vector3<f64>, seed: u64, octaves: u32, persistence: f64, lacunarity: f64) -> f64 {
+    let mut freq = 1.0;
+    let mut amp = 1.0;
+    let mut sum = 0.0;
+    for _ in 0..octaves.max(1) {
+        sum += amp * noise3(p * freq, seed);
+        freq *= lacunarity;
+        amp *= persistence;
+    }
+    sum
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + t * (b - a)
+}
+
+fn grad(hash: usize, x: f64, y: f64, z: f64) -> f64 {
+    match hash & 15 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x + z,
+        5 => -x + z,
+        6 => x - z,
+        7 => -x - z,
+        8 => y + z,
+        9 => -y + z,
+        10 => y - z,
+        11 => -y - z,
+        12 => y + x,
+        13 => -y + z,
+        14 => y - x,
+        _ => -y - z,
+    }
+}
+
+/// Fisher-Yates shuffle of `0..256` driven by `Rng64::new(seed)`.
+fn permutation_table(seed: u64) -> [u8; 256] {
+    let mut table: [u8; 256] = [0; 256];
+    for (i, slot) in table.iter_mut().enumerate() {
+        *slot = i as u8;
+    }
+    let mut rng = Rng64::new(seed);
+    for i in (1..256).rev() {
+        let j = (rng.next_f64() * (i as f64 + 1.0)) as usize % (i + 1);
+        table.swap(i, j);
+    }
+    table
+}
@@ -8,6 +8,14 @@ pub struct ObstaclePoly {
     pub a1: [f64; 3],
     pub a0: [f64; 3],
     pub d: f64,
+    /// Bound on position-estimate error (m); the barrier treats the keep-out
+    /// radius as `d + sigma_p` so it stays valid under localization error.
+    #[serde(default)]
+    pub sigma_p: f64,
+    /// Bound on velocity-estimate error (m/s); worst-case contribution to
+    /// `L_f h` from not knowing the obstacle's true velocity exactly.
+    #[serde(default)]
+    pub sigma_v: f64,
 }
 
 impl ObstaclePoly {
@@ -30,6 +38,82 @@ impl ObstaclePoly {
     pub fn acc(&self) -> Vector3<f64> {
         Vector3::new(2.0 * self.a2[0], 2.0 * self.a2[1], 2.0 * self.a2[2])
     }
+
+    /// Re-parameterize this path so `tau = 0` corresponds to absolute time
+    /// `t0`: `shifted(t0).pos(tau) == self.pos(t0 + tau)`. Used to solve for
+    /// time-of-impact relative to "now" without reworking `time_of_impact`
+    /// itself into an absolute-time root finder.
+    pub(crate) fn shifted(&self, t0: f64) -> ObstaclePoly {
+        let p0 = self.pos(t0);
+        ObstaclePoly {
+            a2: self.a2,
+            a1: [
+                2.0 * self.a2[0] * t0 + self.a1[0],
+                2.0 * self.a2[1] * t0 + self.a1[1],
+                2.0 * self.a2[2] * t0 + self.a1[2],
+            ],
+            a0: [p0.x, p0.y, p0.z],
+            d: self.d,
+            sigma_p: self.sigma_p,
+            sigma_v: self.sigma_v,
+        }
+    }
+
+    /// Earliest time `tau in [0, tau_max]` at which a body moving linearly
+    /// from `body_pos` with constant `body_vel` (relative to this call's time
+    /// origin) first comes within `d + radius` of the obstacle. Forms
+    /// `r(tau) = a2*tau^2 + (a1 - body_vel)*tau + (a0 - body_pos)`, a
+    /// quadratic relative-position path whose squared norm is a quartic in
+    /// `tau`; rather than expanding coefficients, evaluate `|r(tau)|^2`
+    /// directly and bisect the bracket found by a coarse scan for the first
+    /// sign change of `|r(tau)|^2 - (d+radius)^2`. Returns `None` if the two
+    /// never close to within the threshold inside the window.
+    pub fn time_of_impact(
+        &self,
+        body_pos: Vector3<f64>,
+        body_vel: Vector3<f64>,
+        radius: f64,
+        tau_max: f64,
+    ) -> Option<f64> {
+        if !tau_max.is_finite() || tau_max <= 0.0 {
+            return None;
+        }
+        let a2 = Vector3::new(self.a2[0], self.a2[1], self.a2[2]);
+        let a1 = Vector3::new(self.a1[0], self.a1[1], self.a1[2]) - body_vel;
+        let a0 = Vector3::new(self.a0[0], self.a0[1], self.a0[2]) - body_pos;
+        let threshold2 = (self.d + radius).max(0.0).powi(2);
+
+        let f = |tau: f64| -> f64 {
+            let r = a2 * (tau * tau) + a1 * tau + a0;
+            r.norm_squared() - threshold2
+        };
+
+        if f(0.0) <= 0.0 {
+            return Some(0.0);
+        }
+
+        const SAMPLES: usize = 64;
+        let step = tau_max / SAMPLES as f64;
+        let mut prev_tau = 0.0;
+        for k in 1..=SAMPLES {
+            let tau = k as f64 * step;
+            if f(tau) <= 0.0 {
+                let mut lo = prev_tau;
+                let mut hi = tau;
+                for _ in 0..40 {
+                    let mid = 0.5 * (lo + hi);
+                    if f(mid) > 0.0 {
+                        lo = mid;
+                    } else {
+                        hi = mid;
+                    }
+                }
+                return Some(hi);
+            }
+            prev_tau = tau;
+        }
+        None
+    }
 }
 
 pub fn paper_obstacles() -> Vec<ObstaclePoly> {
@@ -39,24 +123,32 @@ pub fn paper_obstacles() -> Vec<ObstaclePoly> {
             a1: [0.0, 0.0, 0.0],
             a0: [47.0, 86.0, 10.0],
             d: 5.0,
+            sigma_p: 0.0,
+            sigma_v: 0.0,
         },
         ObstaclePoly {
             a2: [0.0, 0.0, 0.0],
             a1: [0.0, 0.0, 0.0],
             a0: [52.0, 78.0, 9.0],
             d: 4.0,
+            sigma_p: 0.0,
+            sigma_v: 0.0,
         },
         ObstaclePoly {
             a2: [0.0, 0.0, 0.0],
             a1: [0.0, 0.0, 0.0],
             a0: [43.0, 82.0, 61.5],
             d: 5.0,
+            sigma_p: 0.0,
+            sigma_v: 0.0,
         },
         ObstaclePoly {
             a2: [0.0, 0.0, 0.0],
             a1: [0.0, 0.0, 0.0],
             a0: [49.0, 75.0, 60.5],
             d: 5.5,
+            sigma_p: 0.0,
+            sigma_v: 0.0,
         },
         // moving obstacle: p = [95 - 0.06t, 15 + 0.001 t^2, 100 - 0.089 t]
         ObstaclePoly {
@@ -64,12 +156,16 @@ pub fn paper_obstacles() -> Vec<ObstaclePoly> {
             a1: [-0.06, 0.0, -0.089],
             a0: [95.0, 15.0, 100.0],
             d: 3.0,
+            sigma_p: 0.0,
+            sigma_v: 0.0,
         },
         ObstaclePoly {
             a2: [0.0, 0.0, 0.0],
             a1: [0.0, 0.0, 0.0],
             a0: [69.0, 83.0, 124.5],
             d: 6.0,
+            sigma_p: 0.0,
+            sigma_v: 0.0,
         },
     ]
 }
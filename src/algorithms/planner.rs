@@ -0,0 +1,202 @@
+use crate::algorithms::obstacles::ObstaclePoly;
+use crate::algorithms::qp_project::{project_qp3, Halfspace3};
+use crate::algorithms::rng::Rng64;
+use crate::models::particles::ParticleModel;
+use crate::BodyConfig;
+use nalgebra::Vector3;
+
+/// A candidate trajectory: one acceleration command per simulated step.
+type Genome = Vec<Vector3<f64>>;
+
+/// Search-space and rollout settings for `plan`. The actuator box and
+/// halfspaces are the same shape `project_qp3` already consumes, so every
+/// genome (initial and mutated) can be repaired into a feasible one with the
+/// existing projector instead of a bespoke feasibility check.
+#[derive(Debug, Clone)]
+pub struct PlannerConfig {
+    pub dt: f64,
+    pub horizon: usize,
+    pub box_min: Vector3<f64>,
+    pub box_max: Vector3<f64>,
+    pub constraints: Vec<Halfspace3>,
+    pub qp_iters: usize,
+    pub lambda_collision: f64,
+    pub mu_effort: f64,
+    pub mutation_rate: f64,
+    pub mutation_sigma: f64,
+}
+
+impl Default for PlannerConfig {
+    fn default() -> Self {
+        Self {
+            dt: 1.0 / 30.0,
+            horizon: 60,
+            box_min: Vector3::new(-6.0, -6.0, -6.0),
+            box_max: Vector3::new(6.0, 6.0, 6.0),
+            constraints: Vec::new(),
+            qp_iters: 12,
+            lambda_collision: 20.0,
+            mu_effort: 0.02,
+            mutation_rate: 0.1,
+            mutation_sigma: 0.5,
+        }
+    }
+}
+
+/// Evolve a control sequence driving a body from `start` to `goal` through
+/// `obstacles`, respecting the actuator box/halfspaces in `cfg`. Uses
+/// tournament selection, single-point crossover between two parents, and
+/// per-gene Gaussian mutation; every offspring is repaired through
+/// `project_qp3` after crossover/mutation so the population stays feasible
+/// by construction. Deterministic for a given `seed`.
+pub fn plan(
+    start: [f64; 6],
+    goal: Vector3<f64>,
+    obstacles: &[ObstaclePoly],
+    generations: usize,
+    pop_size: usize,
+    cfg: &PlannerConfig,
+    seed: u64,
+) -> Vec<Vector3<f64>> {
+    let pop_size = pop_size.max(2);
+    let mut rng = Rng64::new(seed);
+
+    let mut population: Vec<Genome> = (0..pop_size)
+        .map(|_| random_genome(cfg, &mut rng))
+        .collect();
+
+    for _ in 0..generations {
+        let fitness: Vec<f64> = population
+            .iter()
+            .map(|g| fitness_of(g, start, goal, obstacles, cfg))
+            .collect();
+
+        let best = best_index(&fitness);
+        let mut next = Vec::with_capacity(pop_size);
+        next.push(population[best].clone());
+
+        while next.len() < pop_size {
+            let p1 = tournament_select(&population, &fitness, &mut rng);
+            let p2 = tournament_select(&population, &fitness, &mut rng);
+            let mut child = crossover(p1, p2, &mut rng);
+            mutate(&mut child, cfg, &mut rng);
+            repair(&mut child, cfg);
+            next.push(child);
+        }
+        population = next;
+    }
+
+    let fitness: Vec<f64> = population
+        .iter()
+        .map(|g| fitness_of(g, start, goal, obstacles, cfg))
+        .collect();
+    population[best_index(&fitness)].clone()
+}
+
+fn random_genome(cfg: &PlannerConfig, rng: &mut Rng64) -> Genome {
+    let mut genome: Genome = (0..cfg.horizon)
+        .map(|_| {
+            Vector3::new(
+                rng.range(cfg.box_min.x, cfg.box_max.x),
+                rng.range(cfg.box_min.y, cfg.box_max.y),
+                rng.range(cfg.box_min.z, cfg.box_max.z),
+            )
+        })
+        .collect();
+    repair(&mut genome, cfg);
+    genome
+}
+
+fn repair(genome: &mut Genome, cfg: &PlannerConfig) {
+    for u in genome.iter_mut() {
+        *u = project_qp3(*u, cfg.box_min, cfg.box_max, &cfg.constraints, cfg.qp_iters);
+    }
+}
+
+fn crossover(p1: &Genome, p2: &Genome, rng: &mut Rng64) -> Genome {
+    let n = p1.len().min(p2.len());
+    if n == 0 {
+        return Vec::new();
+    }
+    let cut = (rng.next_f64() * n as f64) as usize;
+    (0..n)
+        .map(|k| if k < cut { p1[k] } else { p2[k] })
+        .collect()
+}
+
+fn mutate(genome: &mut Genome, cfg: &PlannerConfig, rng: &mut Rng64) {
+    for u in genome.iter_mut() {
+        if rng.next_f64() < cfg.mutation_rate {
+            *u += Vector3::new(
+                rng.next_gaussian() * cfg.mutation_sigma,
+                rng.next_gaussian() * cfg.mutation_sigma,
+                rng.next_gaussian() * cfg.mutation_sigma,
+            );
+        }
+    }
+}
+
+fn tournament_select<'a>(population: &'a [Genome], fitness: &[f64], rng: &mut Rng64) -> &'a Genome {
+    let a = (rng.next_f64() * population.len() as f64) as usize % population.len();
+    let b = (rng.next_f64() * population.len() as f64) as usize % population.len();
+    if fitness[a] >= fitness[b] {
+        &population[a]
+    } else {
+        &population[b]
+    }
+}
+
+fn best_index(fitness: &[f64]) -> usize {
+    let mut best = 0;
+    for (i, f) in fitness.iter().enumerate() {
+        if *f > fitness[best] {
+            best = i;
+        }
+    }
+    best
+}
+
+/// Roll the genome out on a fresh single-body `ParticleModel` and score it:
+/// higher is better. Penalizes distance-to-goal at the horizon, any step
+/// that comes within an obstacle's `d` of its time-parameterized position,
+/// and total control effort.
+fn fitness_of(
+    genome: &Genome,
+    start: [f64; 6],
+    goal: Vector3<f64>,
+    obstacles: &[ObstaclePoly],
+    cfg: &PlannerConfig,
+) -> f64 {
+    let config = BodyConfig {
+        mass: 1.0,
+        state: start,
+        drag_coefficient: 0.0,
+        trajectory_write: false,
+        group: 0,
+        orientation: crate::sim::IDENTITY_ORIENTATION,
+        angular_velocity: [0.0, 0.0, 0.0],
+        inertia: crate::sim::UNIT_INERTIA,
+        radius: crate::sim::DEFAULT_RADIUS,
+    };
+    let mut model = ParticleModel::new(vec![config], cfg.dt);
+
+    let mut collision_penalty = 0.0;
+    let mut effort = 0.0;
+    for u in genome {
+        model.set_force(0, *u);
+        model.step();
+        effort += u.norm_squared();
+
+        let t = model.time();
+        let pos = model.positions()[0];
+        for ob in obstacles {
+            let clearance = (pos - ob.pos(t)).norm() - ob.d;
+            if clearance < 0.0 {
+                collision_penalty += -clearance;
+            }
+        }
+    }
+
+    let dist_to_goal = (model.positions()[0] - goal).norm();
+    -dist_to_goal - cfg.lambda_collision * collision_penalty - cfg.mu_effort * effort
+}
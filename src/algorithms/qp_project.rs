@@ -1,4 +1,4 @@
-use nalgebra::{SVector, Vector3};
+use nalgebra::{DMatrix, DVector, SVector, Vector3};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Halfspace3 {
@@ -92,6 +92,109 @@ fn project_halfspace4(y: SVector<f64, 4>, a: SVector<f64, 4>, b: f64) -> SVector
     }
 }
 
+/// Minimum-norm-to-`x_nom` projection onto the box `[box_min, box_max]`
+/// intersected with the half-spaces `a_i . y >= b_i`, solved with a dual
+/// active-set method instead of `project_qp4`'s fixed-iteration Dykstra
+/// projection. Box bounds are folded into the same constraint list as unit
+/// half-spaces so a single working set drives both. Each iteration solves
+/// the equality-constrained KKT subproblem for the current working set
+/// (`y = x_nom + A_W^T * lambda`, `lambda` from the small `k x k` normal
+/// system `A_W A_W^T lambda = b_W - A_W x_nom`), then either adds the most
+/// violated inactive constraint or drops the active constraint with the
+/// most negative multiplier — converging when both checks pass. `max_iters`
+/// bounds the working-set churn in degenerate/cycling cases.
+pub fn project_qp4_active_set(
+    x_nom: SVector<f64, 4>,
+    box_min: SVector<f64, 4>,
+    box_max: SVector<f64, 4>,
+    constraints: &[Halfspace4],
+    max_iters: usize,
+) -> SVector<f64, 4> {
+    let mut all: Vec<Halfspace4> = Vec::with_capacity(constraints.len() + 8);
+    for k in 0..4 {
+        let mut e = SVector::<f64, 4>::zeros();
+        e[k] = 1.0;
+        all.push(Halfspace4 { a: e, b: box_min[k] });
+        all.push(Halfspace4 { a: -e, b: -box_max[k] });
+    }
+    all.extend_from_slice(constraints);
+
+    let tol = 1.0e-9;
+    let mut active: Vec<usize> = Vec::new();
+    let mut y = x_nom;
+
+    for _ in 0..max_iters.max(1) {
+        let (y_now, lambda) = solve_active_kkt4(x_nom, &all, &active);
+        y = y_now;
+
+        let mut worst: Option<(usize, f64)> = None;
+        for (idx, c) in all.iter().enumerate() {
+            if active.contains(&idx) {
+                continue;
+            }
+            let margin = c.a.dot(&y) - c.b;
+            if margin < -tol && worst.map_or(true, |(_, m)| margin < m) {
+                worst = Some((idx, margin));
+            }
+        }
+        if let Some((idx, _)) = worst {
+            active.push(idx);
+            continue;
+        }
+
+        if let Some((pos, _)) = lambda
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| **l < -tol)
+            .min_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+        {
+            active.remove(pos);
+            continue;
+        }
+
+        return y;
+    }
+    y
+}
+
+/// Solve the equality-constrained KKT subproblem for the working set
+/// `active` (indices into `all`), returning the resulting `y` and the
+/// Lagrange multiplier for each active constraint (same order as `active`).
+fn solve_active_kkt4(
+    x_nom: SVector<f64, 4>,
+    all: &[Halfspace4],
+    active: &[usize],
+) -> (SVector<f64, 4>, Vec<f64>) {
+    if active.is_empty() {
+        return (x_nom, Vec::new());
+    }
+    let k = active.len();
+    let mut a_rows = DMatrix::<f64>::zeros(k, 4);
+    let mut b_vec = DVector::<f64>::zeros(k);
+    for (row, &idx) in active.iter().enumerate() {
+        let c = &all[idx];
+        for col in 0..4 {
+            a_rows[(row, col)] = c.a[col];
+        }
+        b_vec[row] = c.b;
+    }
+
+    let x0 = DVector::from_row_slice(x_nom.as_slice());
+    let g = &a_rows * a_rows.transpose();
+    let rhs = &b_vec - &a_rows * &x0;
+    let lambda = match g.clone().pseudo_inverse(1.0e-9) {
+        Ok(ginv) => ginv * rhs,
+        Err(_) => DVector::zeros(k),
+    };
+
+    let at_lambda = a_rows.transpose() * &lambda;
+    let mut y = x_nom;
+    for i in 0..4 {
+        y[i] += at_lambda[i];
+    }
+    (y, lambda.iter().copied().collect())
+}
+
 fn clamp_vec3(v: Vector3<f64>, min: Vector3<f64>, max: Vector3<f64>) -> Vector3<f64> {
     Vector3::new(
         v.x.max(min.x).min(max.x),
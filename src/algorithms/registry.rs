@@ -0,0 +1,77 @@
+use crate::models::particles::ParticleModel;
+use nalgebra::Vector3;
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use dyn_clone::DynClone;
+
+/// Object-safe control law pluggable into `Engine`. Each implementor
+/// registers a factory in `AlgorithmRegistry` under its own `id()`, so a
+/// downstream crate can add a new controller without editing `engine.rs`.
+pub trait Algorithm: Send + Sync + DynClone {
+    fn apply(&mut self, model: &mut ParticleModel, plane_2d: bool);
+    fn id(&self) -> &'static str;
+    fn name(&self) -> &'static str;
+    fn compatible_models(&self) -> &'static [&'static str];
+
+    /// Downcast target for `Engine::set_*_params`-style typed param access.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    /// Downcast target for `Engine::*_params`-style typed param reads (e.g.
+    /// snapshotting the active algorithm's params without mutating it).
+    fn as_any(&self) -> &dyn Any;
+
+    /// Per-agent attitude debug stream; only `FormationEcbf` populates this.
+    fn attitudes_flat(&self) -> Vec<f32> {
+        Vec::new()
+    }
+    /// Nominal/safe-control debug stream; only `SafeFlockingAlpha` populates this.
+    fn debug_flat(&self) -> Vec<f32> {
+        Vec::new()
+    }
+    /// Reset any algorithm-internal per-agent state (observers, filters, ...).
+    fn reset_agent(&mut self, _index: usize, _pos: Vector3<f64>, _vel: Vector3<f64>) {}
+}
+
+dyn_clone::clone_trait_object!(Algorithm);
+
+type Factory = Box<dyn Fn() -> Box<dyn Algorithm> + Send + Sync>;
+
+/// Process-wide id -> factory map. Built-ins register themselves the first
+/// time `global()` runs; downstream crates call
+/// `AlgorithmRegistry::global().register(...)` to add a controller under a
+/// new id without forking this crate.
+pub struct AlgorithmRegistry {
+    factories: Mutex<HashMap<&'static str, Factory>>,
+}
+
+impl AlgorithmRegistry {
+    pub fn global() -> &'static AlgorithmRegistry {
+        static REGISTRY: OnceLock<AlgorithmRegistry> = OnceLock::new();
+        REGISTRY.get_or_init(|| {
+            let registry = AlgorithmRegistry {
+                factories: Mutex::new(HashMap::new()),
+            };
+            crate::algorithms::builtin::register_all(&registry);
+            registry
+        })
+    }
+
+    pub fn register<F>(&self, id: &'static str, factory: F)
+    where
+        F: Fn() -> Box<dyn Algorithm> + Send + Sync + 'static,
+    {
+        self.factories.lock().unwrap().insert(id, Box::new(factory));
+    }
+
+    pub fn build(&self, id: &str) -> Option<Box<dyn Algorithm>> {
+        self.factories.lock().unwrap().get(id).map(|factory| factory())
+    }
+
+    pub fn ids(&self) -> Vec<&'static str> {
+        let mut ids: Vec<&'static str> = self.factories.lock().unwrap().keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+}
@@ -0,0 +1,65 @@
+/// Small deterministic PRNG shared by the stochastic subsystems in this
+/// crate (rule selection, genetic/annealing search, particle filters,
+/// procedural noise). Seeded explicitly so tests and replays are
+/// reproducible; not cryptographically secure.
+#[derive(Debug, Clone)]
+pub struct Rng64 {
+    state: u64,
+}
+
+impl Rng64 {
+    pub fn new(seed: u64) -> Self {
+        // Avoid the fixed point at 0.
+        Self { state: seed ^ 0x9E3779B97F4A7C15 | 1 }
+    }
+
+    /// SplitMix64 step.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+
+    /// Uniform value in `[min, max)`.
+    pub fn range(&mut self, min: f64, max: f64) -> f64 {
+        min + self.next_f64() * (max - min)
+    }
+
+    /// Standard normal sample via the Box-Muller transform.
+    pub fn next_gaussian(&mut self) -> f64 {
+        let u1 = self.next_f64().max(1.0e-12);
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+
+    /// Pick an index with probability proportional to `weights`. Falls back
+    /// to a uniform pick over the slice when every weight is non-positive.
+    pub fn weighted_index(&mut self, weights: &[f64]) -> usize {
+        let total: f64 = weights.iter().filter(|w| **w > 0.0).sum();
+        if total <= 0.0 || weights.is_empty() {
+            return if weights.is_empty() {
+                0
+            } else {
+                (self.next_f64() * weights.len() as f64) as usize
+            };
+        }
+        let mut pick = self.next_f64() * total;
+        for (i, w) in weights.iter().enumerate() {
+            if *w <= 0.0 {
+                continue;
+            }
+            if pick < *w {
+                return i;
+            }
+            pick -= *w;
+        }
+        weights.len() - 1
+    }
+}
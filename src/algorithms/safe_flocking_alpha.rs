@@ -1,13 +1,19 @@
+use crate::algorithms::estimation::{gaussian_likelihood, ParticleFilter};
 use crate::algorithms::flocking::{
     DEFAULT_BOUNDARY_RADIUS, DEFAULT_BOUNDARY_WEIGHT, DEFAULT_MAX_FORCE, DEFAULT_MAX_SPEED,
     DEFAULT_SPEED_LIMIT,
 };
 use crate::algorithms::obstacles::{paper_obstacles, ObstaclePoly};
-use crate::algorithms::qp_project::{project_qp4, Halfspace4};
+use crate::algorithms::qp_project::{project_qp4, project_qp4_active_set, Halfspace4};
+use crate::algorithms::spatial_grid::SpatialGrid;
 use crate::models::particles::ParticleModel;
 use nalgebra::{SVector, Vector3};
 use serde::{Deserialize, Serialize};
 
+pub const DEFAULT_ESTIMATION_PARTICLES: usize = 1500;
+pub const DEFAULT_ESTIMATION_WIND_STD: f64 = 0.3;
+pub const DEFAULT_ESTIMATION_MEASUREMENT_STD: f64 = 0.2;
+
 pub const DEFAULT_SAFE_ALPHA_NEIGHBOR_RADIUS: f64 = 2.6;
 pub const DEFAULT_SAFE_ALPHA_DESIRED_DISTANCE: f64 = 1.4;
 pub const DEFAULT_SAFE_ALPHA_SIGMA_EPS: f64 = 0.1;
@@ -20,6 +26,24 @@ pub const DEFAULT_SAFE_ALPHA_ALIGNMENT_WEIGHT: f64 = 0.65;
 const DEFAULT_QP_ITERS: usize = 14;
 const DEFAULT_EPS: f64 = 1.0e-2;
 
+/// Which method `filter_u` uses to project the nominal control onto the
+/// box + CBF half-space feasible set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QpSolver {
+    /// Fixed-iteration Dykstra-style alternating projection (`project_qp4`):
+    /// cheap, may leave constraints mildly violated under heavy overlap.
+    Projection,
+    /// Dual active-set solver (`project_qp4_active_set`): exact to solver
+    /// tolerance, more work per call when many constraints are active.
+    ActiveSet,
+}
+
+impl Default for QpSolver {
+    fn default() -> Self {
+        QpSolver::Projection
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct SafeFlockAlphaParams {
@@ -61,8 +85,37 @@ pub struct SafeFlockAlphaParams {
 
     pub smooth_eps: f64,
     pub qp_iters: usize,
+    pub qp_solver: QpSolver,
 
     pub obstacles: Vec<ObstaclePoly>,
+
+    /// Optional migration target (position, velocity). When set,
+    /// `compute_nominal` steers toward the target's *predicted* future
+    /// location rather than its current one, so the flock can intercept a
+    /// moving goal instead of trailing it.
+    pub migration_goal: Option<([f64; 3], [f64; 3])>,
+    pub migration_weight: f64,
+
+    /// Receding-horizon nominal mode: instead of the instantaneous
+    /// alpha-lattice force, track an exponentially-decaying reference
+    /// trajectory toward `migration_goal` (or the current state, if unset)
+    /// and drive `u_nom` toward its first step.
+    pub use_mpc_reference: bool,
+    pub mpc_horizon: usize,
+    pub mpc_pos_decay: f64,
+    pub mpc_vel_decay: f64,
+    pub mpc_pos_gain: f64,
+    pub mpc_vel_gain: f64,
+
+    /// Estimate each agent's (pos, vel) with a particle filter instead of
+    /// trusting `model.positions()`/`model.velocities()` directly — for
+    /// scenes where those readings stand in for a noisy sensor and the agent
+    /// is subject to an unmodeled disturbance (e.g. wind).
+    pub use_estimation: bool,
+    pub estimation_particles: usize,
+    pub estimation_wind_std: f64,
+    pub estimation_measurement_std: f64,
+    pub estimation_seed: u64,
 }
 
 impl Default for SafeFlockAlphaParams {
@@ -97,7 +150,21 @@ impl Default for SafeFlockAlphaParams {
             slack_max: 50.0,
             smooth_eps: DEFAULT_EPS,
             qp_iters: DEFAULT_QP_ITERS,
+            qp_solver: QpSolver::Projection,
             obstacles: paper_obstacles(),
+            migration_goal: None,
+            migration_weight: 0.3,
+            use_mpc_reference: false,
+            mpc_horizon: 10,
+            mpc_pos_decay: 1.5,
+            mpc_vel_decay: 1.5,
+            mpc_pos_gain: 1.0,
+            mpc_vel_gain: 1.0,
+            use_estimation: false,
+            estimation_particles: DEFAULT_ESTIMATION_PARTICLES,
+            estimation_wind_std: DEFAULT_ESTIMATION_WIND_STD,
+            estimation_measurement_std: DEFAULT_ESTIMATION_MEASUREMENT_STD,
+            estimation_seed: 0,
         }
     }
 }
@@ -115,6 +182,7 @@ struct SafeFlockAlphaState {
     slack: Vec<f64>,
     active: Vec<f64>,
     total: Vec<f64>,
+    estimators: Vec<Option<ParticleFilter>>,
 }
 
 impl SafeFlockingAlpha {
@@ -168,11 +236,26 @@ impl SafeFlockingAlpha {
 
         self.ensure_state(n);
 
+        if self.params.use_estimation {
+            self.estimate_states(&mut positions, &mut velocities, dt);
+        }
+
         let masses = model.masses().to_vec();
         let drags = model.drags().to_vec();
 
+        // One spatial index per `apply` call, shared by the nominal-force
+        // pass and every `filter_u` call: cell size covers both radii so a
+        // single 27-cell (9 when `plane_2d`, handled by the z=0 positions)
+        // neighborhood visit is enough for either query.
+        let cell_size = self
+            .params
+            .neighbor_radius
+            .max(self.params.cbf_neighbor_radius)
+            .max(1.0e-6);
+        let grid = SpatialGrid::build(&positions, cell_size);
+
         // Precompute nominal u_n for everyone (needed for agent-agent CBF as neighbor prediction).
-        self.compute_nominal(&positions, &velocities, plane_2d);
+        self.compute_nominal(&positions, &velocities, plane_2d, dt, &grid);
 
         // Neighbor acceleration prediction for inter-agent CBF:
         // use last applied safe u (more consistent than u_nom when filter is active).
@@ -211,6 +294,7 @@ impl SafeFlockingAlpha {
                     gamma_i,
                     u_nom,
                     &u_pred,
+                    &grid,
                 );
 
                 let mut u_cmd = u_safe;
@@ -249,9 +333,47 @@ impl SafeFlockingAlpha {
         self.state.slack = vec![0.0; n];
         self.state.active = vec![0.0; n];
         self.state.total = vec![0.0; n];
+        self.state.estimators = (0..n).map(|_| None).collect();
+    }
+
+    /// Replace the raw `positions`/`velocities` readings with each agent's
+    /// particle-filter belief: predict with the last commanded acceleration
+    /// plus sampled wind, treat the raw reading as a noisy measurement, then
+    /// update/resample and read back the weighted mean.
+    fn estimate_states(&mut self, positions: &mut [Vector3<f64>], velocities: &mut [Vector3<f64>], dt: f64) {
+        let count = self.params.estimation_particles.max(1);
+        let wind_std = self.params.estimation_wind_std.max(0.0);
+        let sigma = self.params.estimation_measurement_std.max(1.0e-6);
+
+        for i in 0..positions.len() {
+            let measurement = positions[i];
+            let filter = self.state.estimators[i].get_or_insert_with(|| {
+                ParticleFilter::new(
+                    measurement,
+                    velocities[i],
+                    count,
+                    wind_std,
+                    self.params.estimation_seed.wrapping_add(i as u64),
+                )
+            });
+
+            let u_prev = self.state.u_safe.get(i).copied().unwrap_or(Vector3::new(0.0, 0.0, 0.0));
+            filter.predict(dt, u_prev);
+            filter.update(|pos| gaussian_likelihood(pos, measurement, sigma));
+
+            positions[i] = filter.mean_position();
+            velocities[i] = filter.mean_velocity();
+        }
     }
 
-    fn compute_nominal(&mut self, positions: &[Vector3<f64>], velocities: &[Vector3<f64>], plane_2d: bool) {
+    fn compute_nominal(
+        &mut self,
+        positions: &[Vector3<f64>],
+        velocities: &[Vector3<f64>],
+        plane_2d: bool,
+        dt: f64,
+        grid: &SpatialGrid,
+    ) {
         let n = positions.len();
         let mut r = self.params.neighbor_radius;
         let d = self.params.desired_distance;
@@ -297,37 +419,51 @@ impl SafeFlockingAlpha {
             let pos_i = positions[i];
             let vel_i = velocities[i];
 
-            let mut grad_sum = Vector3::new(0.0, 0.0, 0.0);
-            let mut cons_sum = Vector3::new(0.0, 0.0, 0.0);
-            let mut neighbors = 0usize;
+            let mut u = if self.params.use_mpc_reference {
+                self.mpc_reference_force(pos_i, vel_i)
+            } else {
+                let mut grad_sum = Vector3::new(0.0, 0.0, 0.0);
+                let mut cons_sum = Vector3::new(0.0, 0.0, 0.0);
+                let mut neighbors = 0usize;
 
-            for j in 0..n {
-                if i == j {
-                    continue;
-                }
-                let diff = positions[j] - pos_i;
-                let dist2 = diff.norm_squared();
-                if dist2 > neighbor_r2 {
-                    continue;
-                }
-                neighbors += 1;
+                for j in grid.neighbors(i, positions, r) {
+                    let diff = positions[j] - pos_i;
+                    let dist2 = diff.norm_squared();
+                    debug_assert!(dist2 <= neighbor_r2);
+                    neighbors += 1;
 
-                let denom = (1.0 + eps * dist2).sqrt();
-                let z = (denom - 1.0) / eps;
-                let nij = if denom > 0.0 { diff / denom } else { diff };
+                    let denom = (1.0 + eps * dist2).sqrt();
+                    let z = (denom - 1.0) / eps;
+                    let nij = if denom > 0.0 { diff / denom } else { diff };
 
-                let varphi = phi_alpha(z, d_alpha, r_alpha, h, a, b);
-                grad_sum += nij * varphi;
+                    let varphi = phi_alpha(z, d_alpha, r_alpha, h, a, b);
+                    grad_sum += nij * varphi;
 
-                let aij = bump_rho(if r_alpha > 0.0 { z / r_alpha } else { 0.0 }, h);
-                cons_sum += (velocities[j] - vel_i) * aij;
-            }
+                    let aij = bump_rho(if r_alpha > 0.0 { z / r_alpha } else { 0.0 }, h);
+                    cons_sum += (velocities[j] - vel_i) * aij;
+                }
 
-            if neighbors > 0 {
-                cons_sum *= 1.0 / neighbors as f64;
-            }
+                if neighbors > 0 {
+                    cons_sum *= 1.0 / neighbors as f64;
+                }
+
+                let mut u = grad_sum * self.params.alpha_weight + cons_sum * self.params.alignment_weight;
+
+                // Migration toward a moving goal: aim at its predicted future
+                // location instead of its current one so the flock intercepts
+                // rather than trails it.
+                if let Some((goal_pos, goal_vel)) = self.params.migration_goal {
+                    let goal_pos = vec3_from(goal_pos);
+                    let goal_vel = vec3_from(goal_vel);
+                    let dist_to_goal = (goal_pos - pos_i).norm();
+                    let lead_time =
+                        dist_to_goal / (self.params.max_speed.max(1.0e-6) * dt.max(1.0e-9));
+                    let predicted = goal_pos + goal_vel * lead_time;
+                    u += (predicted - pos_i) * self.params.migration_weight;
+                }
 
-            let mut u = grad_sum * self.params.alpha_weight + cons_sum * self.params.alignment_weight;
+                u
+            };
 
             // Soft boundary centered at origin.
             let dist = pos_i.norm();
@@ -356,6 +492,44 @@ impl SafeFlockingAlpha {
         }
     }
 
+    /// Exponential-decay reference trajectory from `(pos_i, vel_i)` toward
+    /// `migration_goal` (or the current state, if unset): `x_ref(k) = A *
+    /// exp(B*k) + C` for `k = 0..=mpc_horizon`, with separate decay rates
+    /// for position and velocity. Index 0 is always `(pos_i, vel_i)`.
+    fn mpc_reference_trajectory(
+        &self,
+        pos_i: Vector3<f64>,
+        vel_i: Vector3<f64>,
+    ) -> Vec<(Vector3<f64>, Vector3<f64>)> {
+        let (goal_pos, goal_vel) = match self.params.migration_goal {
+            Some((p, v)) => (vec3_from(p), vec3_from(v)),
+            None => (pos_i, Vector3::new(0.0, 0.0, 0.0)),
+        };
+        let b_pos = -self.params.mpc_pos_decay.abs().max(1.0e-6);
+        let b_vel = -self.params.mpc_vel_decay.abs().max(1.0e-6);
+        let a_pos = pos_i - goal_pos;
+        let a_vel = vel_i - goal_vel;
+
+        (0..=self.params.mpc_horizon)
+            .map(|k| {
+                let k = k as f64;
+                (
+                    a_pos * (b_pos * k).exp() + goal_pos,
+                    a_vel * (b_vel * k).exp() + goal_vel,
+                )
+            })
+            .collect()
+    }
+
+    /// Nominal force for the receding-horizon mode: drive toward the first
+    /// step of `mpc_reference_trajectory` rather than the instantaneous
+    /// alpha-lattice force.
+    fn mpc_reference_force(&self, pos_i: Vector3<f64>, vel_i: Vector3<f64>) -> Vector3<f64> {
+        let trajectory = self.mpc_reference_trajectory(pos_i, vel_i);
+        let (pos_ref, vel_ref) = trajectory.get(1).copied().unwrap_or((pos_i, vel_i));
+        (pos_ref - pos_i) * self.params.mpc_pos_gain + (vel_ref - vel_i) * self.params.mpc_vel_gain
+    }
+
     fn filter_u(
         &self,
         i: usize,
@@ -369,6 +543,7 @@ impl SafeFlockingAlpha {
         gamma_i: f64,
         u_nom: Vector3<f64>,
         u_pred: &[Vector3<f64>],
+        grid: &SpatialGrid,
     ) -> (Vector3<f64>, f64, f64, f64) {
         let mut constraints: Vec<Halfspace4> = Vec::new();
 
@@ -427,22 +602,17 @@ impl SafeFlockingAlpha {
         }
 
         if self.params.use_agent_cbf && positions.len() == velocities.len() {
-            let n = positions.len();
             let mut rr = self.params.cbf_neighbor_radius;
             if !rr.is_finite() || rr <= 0.0 {
                 rr = self.params.neighbor_radius.max(0.0);
             }
-            let rr2 = rr * rr;
             let d_safe2 = self.params.agent_safe_distance.max(0.0).powi(2);
 
-            for j in 0..n {
-                if j == i {
-                    continue;
-                }
+            for j in grid.neighbors(i, positions, rr) {
                 let p_j = positions[j];
                 let r = p_i - p_j;
                 let r2 = r.norm_squared();
-                if r2 <= 1.0e-10 || r2 > rr2 {
+                if r2 <= 1.0e-10 {
                     continue;
                 }
                 let v_j = velocities[j];
@@ -475,7 +645,18 @@ impl SafeFlockingAlpha {
         let box_min = SVector::<f64, 4>::new(umin.x, umin.y, umin.z, 0.0);
         let box_max = SVector::<f64, 4>::new(umax.x, umax.y, umax.z, sigma * self.params.slack_max.max(0.0));
 
-        let y = project_qp4(y_nom, box_min, box_max, &constraints, self.params.qp_iters);
+        let y = match self.params.qp_solver {
+            QpSolver::Projection => {
+                project_qp4(y_nom, box_min, box_max, &constraints, self.params.qp_iters)
+            }
+            QpSolver::ActiveSet => project_qp4_active_set(
+                y_nom,
+                box_min,
+                box_max,
+                &constraints,
+                self.params.qp_iters.max(24),
+            ),
+        };
         let u = Vector3::new(y[0], y[1], y[2]);
         let slack = (y[3] / sigma).max(0.0);
 
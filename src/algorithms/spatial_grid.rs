@@ -0,0 +1,76 @@
+use nalgebra::Vector3;
+use std::collections::HashMap;
+
+/// Uniform spatial-hash grid over 3-D points. Turns an O(N^2) neighbor scan
+/// into a roughly O(N) one when the query radius is small relative to the
+/// swarm's bounding volume: each point is hashed into a cell sized to the
+/// interaction radius, and neighbor queries only visit the cell ring around
+/// the query point instead of every other point.
+#[derive(Debug, Clone)]
+pub struct SpatialGrid {
+    cell_size: f64,
+    cells: HashMap<(i32, i32, i32), Vec<usize>>,
+}
+
+impl SpatialGrid {
+    /// Build a grid over `positions` with cells of side `cell_size`. A
+    /// non-finite or non-positive `cell_size` falls back to 1.0 so the grid
+    /// degrades gracefully instead of dividing by zero.
+    pub fn build(positions: &[Vector3<f64>], cell_size: f64) -> Self {
+        let cell_size = if cell_size.is_finite() && cell_size > 0.0 {
+            cell_size
+        } else {
+            1.0
+        };
+        let mut cells: HashMap<(i32, i32, i32), Vec<usize>> =
+            HashMap::with_capacity(positions.len());
+        for (i, p) in positions.iter().enumerate() {
+            cells.entry(cell_of(*p, cell_size)).or_default().push(i);
+        }
+        Self { cell_size, cells }
+    }
+
+    /// Indices of points within `radius` of `positions[i]`, excluding `i`
+    /// itself. Visits the 3x3x3 cell block around `i` when `radius` fits in
+    /// one cell, widening to `ceil(radius / cell_size)` rings otherwise, then
+    /// applies the exact squared-distance test to the candidates.
+    pub fn neighbors(&self, i: usize, positions: &[Vector3<f64>], radius: f64) -> Vec<usize> {
+        let mut out = Vec::new();
+        if i >= positions.len() || radius <= 0.0 {
+            return out;
+        }
+        let p_i = positions[i];
+        let radius2 = radius * radius;
+        let rings = ((radius / self.cell_size).ceil() as i32).max(1);
+        let (cx, cy, cz) = cell_of(p_i, self.cell_size);
+
+        for dx in -rings..=rings {
+            for dy in -rings..=rings {
+                for dz in -rings..=rings {
+                    let key = (cx + dx, cy + dy, cz + dz);
+                    let Some(bucket) = self.cells.get(&key) else {
+                        continue;
+                    };
+                    for &j in bucket {
+                        if j == i {
+                            continue;
+                        }
+                        let dist2 = (positions[j] - p_i).norm_squared();
+                        if dist2 <= radius2 {
+                            out.push(j);
+                        }
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+fn cell_of(p: Vector3<f64>, cell_size: f64) -> (i32, i32, i32) {
+    (
+        (p.x / cell_size).floor() as i32,
+        (p.y / cell_size).floor() as i32,
+        (p.z / cell_size).floor() as i32,
+    )
+}
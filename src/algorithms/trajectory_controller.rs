@@ -0,0 +1,106 @@
+use nalgebra::{SVector, Vector3};
+
+pub const DEFAULT_TRAJ_HORIZON: usize = 20;
+pub const DEFAULT_TRAJ_POS_DECAY: f64 = -0.5;
+pub const DEFAULT_TRAJ_VEL_DECAY: f64 = -2.0;
+pub const DEFAULT_TRAJ_POS_GAIN: f64 = 4.0;
+pub const DEFAULT_TRAJ_VEL_GAIN: f64 = 2.0;
+pub const DEFAULT_TRAJ_MAX_FORCE: f64 = 20.0;
+
+/// Drives a `Point`/`ParticleModel` body smoothly toward a 6-D setpoint
+/// `[x, y, z, vx, vy, vz]` by tracking an exponential reference profile
+/// instead of reacting to the raw error: `ref(dof, h) = A * exp(B*h) + C`
+/// for horizon step `h`, with `C` the target, `A` the offset from the
+/// current state, and a steeper (more negative) decay `B` on velocity
+/// channels than position channels, so the planned state asymptotically
+/// approaches the target without overshoot.
+#[derive(Debug, Clone)]
+pub struct TrajectoryController {
+    pub horizon: usize,
+    pub pos_decay: f64,
+    pub vel_decay: f64,
+    pub pos_gain: f64,
+    pub vel_gain: f64,
+    pub max_force: f64,
+    reference: SVector<f64, 6>,
+}
+
+impl Default for TrajectoryController {
+    fn default() -> Self {
+        Self {
+            horizon: DEFAULT_TRAJ_HORIZON,
+            pos_decay: DEFAULT_TRAJ_POS_DECAY,
+            vel_decay: DEFAULT_TRAJ_VEL_DECAY,
+            pos_gain: DEFAULT_TRAJ_POS_GAIN,
+            vel_gain: DEFAULT_TRAJ_VEL_GAIN,
+            max_force: DEFAULT_TRAJ_MAX_FORCE,
+            reference: SVector::<f64, 6>::zeros(),
+        }
+    }
+}
+
+impl TrajectoryController {
+    pub fn new(reference: [f64; 6]) -> Self {
+        Self {
+            reference: SVector::from_row_slice(&reference),
+            ..Default::default()
+        }
+    }
+
+    pub fn set_reference(&mut self, reference: [f64; 6]) {
+        self.reference = SVector::from_row_slice(&reference);
+    }
+
+    pub fn reference(&self) -> [f64; 6] {
+        let mut out = [0.0; 6];
+        out.copy_from_slice(self.reference.as_slice());
+        out
+    }
+
+    pub fn horizon(&self) -> usize {
+        self.horizon
+    }
+
+    /// Exponential-decay reference trajectory from `state` toward
+    /// `self.reference`: `x_ref(k) = A * exp(B*k) + C` for `k = 0..=horizon`,
+    /// with separate decay rates for the position and velocity channels.
+    /// Index 0 is always `state` itself.
+    pub fn reference_trajectory(&self, state: [f64; 6]) -> Vec<[f64; 6]> {
+        let b_pos = -self.pos_decay.abs().max(1.0e-6);
+        let b_vel = -self.vel_decay.abs().max(1.0e-6);
+
+        (0..=self.horizon)
+            .map(|k| {
+                let k = k as f64;
+                let mut out = [0.0; 6];
+                for (dof, slot) in out.iter_mut().enumerate() {
+                    let b = if dof < 3 { b_pos } else { b_vel };
+                    let c = self.reference[dof];
+                    let a = state[dof] - c;
+                    *slot = a * (b * k).exp() + c;
+                }
+                out
+            })
+            .collect()
+    }
+
+    /// Force for `Point::step`/`ParticleModel::set_force` that tracks the
+    /// next step of `reference_trajectory`: proportional-plus-feedforward on
+    /// position and velocity error, clamped to `max_force`.
+    pub fn force(&self, state: [f64; 6]) -> Vector3<f64> {
+        let trajectory = self.reference_trajectory(state);
+        let next = trajectory.get(1).copied().unwrap_or(state);
+
+        let pos = Vector3::new(state[0], state[1], state[2]);
+        let vel = Vector3::new(state[3], state[4], state[5]);
+        let pos_ref = Vector3::new(next[0], next[1], next[2]);
+        let vel_ref = Vector3::new(next[3], next[4], next[5]);
+
+        let mut force = (pos_ref - pos) * self.pos_gain + (vel_ref - vel) * self.vel_gain;
+        let fmag = force.norm();
+        if fmag > self.max_force && fmag > 0.0 {
+            force = force / fmag * self.max_force;
+        }
+        force
+    }
+}
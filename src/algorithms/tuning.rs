@@ -0,0 +1,314 @@
+use crate::algorithms::formation_ecbf::{FormationEcbf, FormationEcbfParams};
+use crate::algorithms::rng::Rng64;
+use crate::algorithms::safe_flocking_alpha::{SafeFlockAlphaParams, SafeFlockingAlpha};
+use crate::models::particles::ParticleModel;
+use crate::BodyConfig;
+use nalgebra::Vector3;
+
+type Getter = fn(&SafeFlockAlphaParams) -> f64;
+type Setter = fn(&mut SafeFlockAlphaParams, f64);
+
+/// Tunable knob: accessor pair plus the range simulated annealing is allowed
+/// to explore it in.
+const TUNABLES: &[(Getter, Setter, f64, f64)] = &[
+    (|p| p.lambda1, |p, v| p.lambda1 = v, 0.1, 8.0),
+    (|p| p.lambda2, |p, v| p.lambda2 = v, 0.1, 8.0),
+    (|p| p.alpha_weight, |p, v| p.alpha_weight = v, 0.1, 4.0),
+    (|p| p.alignment_weight, |p, v| p.alignment_weight = v, 0.0, 2.0),
+    (|p| p.desired_distance, |p, v| p.desired_distance = v, 0.3, 4.0),
+    (
+        |p| p.agent_safe_distance,
+        |p, v| p.agent_safe_distance = v,
+        0.2,
+        3.0,
+    ),
+    (|p| p.boundary_weight, |p, v| p.boundary_weight = v, 0.0, 2.0),
+];
+
+/// Simulated-annealing schedule and rollout sizing for `anneal_params`.
+#[derive(Debug, Clone)]
+pub struct AnnealConfig {
+    pub t0: f64,
+    pub t1: f64,
+    pub iters: usize,
+    pub rollout_steps: usize,
+    pub seed: u64,
+}
+
+impl Default for AnnealConfig {
+    fn default() -> Self {
+        Self {
+            t0: 1.0,
+            t1: 0.01,
+            iters: 200,
+            rollout_steps: 120,
+            seed: 0,
+        }
+    }
+}
+
+/// Search `SafeFlockAlphaParams` for a setting that minimizes `cost` (lower
+/// is better) over a short rollout of `configs` under `dt`, using classic
+/// simulated annealing: each iteration perturbs one randomly-chosen tunable
+/// with a Gaussian step, re-rolls out, and accepts the candidate outright if
+/// it improves the cost or with probability `exp((old - new) / T)`
+/// otherwise. Temperature follows the geometric schedule
+/// `T(k) = t0^(1-t) * t1^t` with `t = k / iters`. Returns the best params
+/// found, not necessarily the last accepted ones.
+pub fn anneal_params(
+    initial: &SafeFlockAlphaParams,
+    configs: &[BodyConfig],
+    dt: f64,
+    cost: impl Fn(&ParticleModel) -> f64,
+    cfg: &AnnealConfig,
+) -> SafeFlockAlphaParams {
+    let mut rng = Rng64::new(cfg.seed);
+    let iters = cfg.iters.max(1);
+
+    let mut current = initial.clone();
+    let mut current_cost = rollout_cost(&current, configs, dt, cfg.rollout_steps, &cost);
+    let mut best = current.clone();
+    let mut best_cost = current_cost;
+
+    for k in 0..iters {
+        let t = k as f64 / iters as f64;
+        let temp = cfg.t0.max(1.0e-9).powf(1.0 - t) * cfg.t1.max(1.0e-9).powf(t);
+
+        let slot = (rng.next_f64() * TUNABLES.len() as f64) as usize % TUNABLES.len();
+        let (get, set, lo, hi) = TUNABLES[slot];
+
+        let mut candidate = current.clone();
+        let step = (hi - lo) * 0.1;
+        let perturbed = (get(&current) + rng.next_gaussian() * step).clamp(lo, hi);
+        set(&mut candidate, perturbed);
+
+        let candidate_cost = rollout_cost(&candidate, configs, dt, cfg.rollout_steps, &cost);
+        let accept = candidate_cost <= current_cost
+            || rng.next_f64() < ((current_cost - candidate_cost) / temp).exp();
+
+        if accept {
+            current = candidate;
+            current_cost = candidate_cost;
+            if current_cost < best_cost {
+                best_cost = current_cost;
+                best = current.clone();
+            }
+        }
+    }
+
+    best
+}
+
+fn rollout_cost(
+    params: &SafeFlockAlphaParams,
+    configs: &[BodyConfig],
+    dt: f64,
+    steps: usize,
+    cost: &impl Fn(&ParticleModel) -> f64,
+) -> f64 {
+    let mut model = ParticleModel::new(configs.to_vec(), dt);
+    let mut controller = SafeFlockingAlpha::new(params.clone());
+    let mut total = 0.0;
+    for _ in 0..steps.max(1) {
+        controller.apply(&mut model, false);
+        model.step();
+        total += cost(&model);
+    }
+    total
+}
+
+type EcbfGetter = fn(&FormationEcbfParams) -> f64;
+type EcbfSetter = fn(&mut FormationEcbfParams, f64);
+
+/// Tunable gene: accessor pair plus the `[lo, hi]` range `evolve_formation_params`
+/// is allowed to explore it in.
+const ECBF_TUNABLES: &[(EcbfGetter, EcbfSetter, f64, f64)] = &[
+    (|p| p.k1, |p, v| p.k1 = v, 0.1, 8.0),
+    (|p| p.k2, |p, v| p.k2 = v, 0.1, 8.0),
+    (|p| p.gamma1, |p, v| p.gamma1 = v, 0.0, 3.0),
+    (|p| p.gamma2, |p, v| p.gamma2 = v, 0.0, 3.0),
+    (|p| p.obs_k1, |p, v| p.obs_k1 = v, 0.1, 5.0),
+    (|p| p.obs_k2, |p, v| p.obs_k2 = v, 0.1, 5.0),
+    (|p| p.obs_k3, |p, v| p.obs_k3 = v, 0.1, 5.0),
+    (|p| p.obs_a1, |p, v| p.obs_a1 = v, 0.5, 4.0),
+    (|p| p.obs_a2, |p, v| p.obs_a2 = v, 0.5, 4.0),
+    (|p| p.obs_b1, |p, v| p.obs_b1 = v, 0.5, 4.0),
+    (|p| p.obs_b2, |p, v| p.obs_b2 = v, 0.5, 4.0),
+    (|p| p.do_kappa1, |p, v| p.do_kappa1 = v, 0.1, 8.0),
+    (|p| p.do_kappa2, |p, v| p.do_kappa2 = v, 0.1, 8.0),
+    (|p| p.do_kappa3, |p, v| p.do_kappa3 = v, 0.1, 8.0),
+    (|p| p.do_eta1, |p, v| p.do_eta1 = v, 0.1, 4.0),
+    (|p| p.do_eta2, |p, v| p.do_eta2 = v, 0.1, 4.0),
+    (|p| p.do_eta3, |p, v| p.do_eta3 = v, 0.0, 2.0),
+    (|p| p.lambda1, |p, v| p.lambda1 = v, 0.5, 6.0),
+    (|p| p.lambda2, |p, v| p.lambda2 = v, 0.5, 6.0),
+];
+
+/// Population/mutation sizing and fitness weights for `evolve_formation_params`.
+#[derive(Debug, Clone)]
+pub struct EvolveConfig {
+    pub population: usize,
+    pub generations: usize,
+    pub elite_fraction: f64,
+    pub mutation_sigma: f64,
+    pub rollout_steps: usize,
+    /// Weight on mean per-agent `|p - chi - offset|` tracking error.
+    pub weight_tracking: f64,
+    /// Weight on the integrated negative part of the obstacle margin `h`
+    /// (any barrier violation).
+    pub weight_clearance: f64,
+    /// Weight on mean commanded-force magnitude (control effort).
+    pub weight_effort: f64,
+    pub seed: u64,
+}
+
+impl Default for EvolveConfig {
+    fn default() -> Self {
+        Self {
+            population: 24,
+            generations: 40,
+            elite_fraction: 0.25,
+            mutation_sigma: 0.1,
+            rollout_steps: 120,
+            weight_tracking: 1.0,
+            weight_clearance: 5.0,
+            weight_effort: 0.01,
+            seed: 0,
+        }
+    }
+}
+
+/// Genetic search over the tunable subset of `FormationEcbfParams` (the
+/// nominal/observer/disturbance-observer/ECBF gains in `ECBF_TUNABLES`):
+/// each generation evaluates every population member by rolling out
+/// `FormationEcbf::apply` for `rollout_steps` frames on a cloned
+/// `ParticleModel` built from `configs`, scoring fitness as a weighted sum
+/// of mean formation-tracking error, integrated obstacle-barrier violation,
+/// and mean control effort (lower is better). The top `elite_fraction` carry
+/// over unchanged, the rest of the next generation is produced by uniform
+/// crossover of two randomly-chosen elites followed by Gaussian mutation,
+/// genes clamped to their `ECBF_TUNABLES` range throughout. Returns the
+/// best-scoring params seen across all generations, not necessarily the
+/// last generation's.
+pub fn evolve_formation_params(
+    base: &FormationEcbfParams,
+    configs: &[BodyConfig],
+    dt: f64,
+    cfg: &EvolveConfig,
+) -> FormationEcbfParams {
+    let mut rng = Rng64::new(cfg.seed);
+    let pop_size = cfg.population.max(2);
+    let elite_n = ((pop_size as f64 * cfg.elite_fraction.clamp(0.0, 1.0)).round() as usize).clamp(1, pop_size);
+
+    let base_genes = ecbf_encode(base);
+    let mut population: Vec<Vec<f64>> = (0..pop_size)
+        .map(|i| {
+            if i == 0 {
+                base_genes.clone()
+            } else {
+                ecbf_mutate(&base_genes, cfg.mutation_sigma, &mut rng)
+            }
+        })
+        .collect();
+
+    let mut best_genes = base_genes;
+    let mut best_fitness = f64::INFINITY;
+
+    for _ in 0..cfg.generations.max(1) {
+        let mut scored: Vec<(f64, Vec<f64>)> = population
+            .iter()
+            .map(|genes| {
+                let params = ecbf_decode(base, genes);
+                let fitness = ecbf_rollout_fitness(&params, configs, dt, cfg);
+                (fitness, genes.clone())
+            })
+            .collect();
+        scored.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        if scored[0].0 < best_fitness {
+            best_fitness = scored[0].0;
+            best_genes = scored[0].1.clone();
+        }
+
+        let elites: Vec<&Vec<f64>> = scored.iter().take(elite_n).map(|(_, g)| g).collect();
+
+        let mut next_gen: Vec<Vec<f64>> = elites.iter().map(|g| (*g).clone()).collect();
+        while next_gen.len() < pop_size {
+            let a = elites[(rng.next_f64() * elites.len() as f64) as usize % elites.len()];
+            let b = elites[(rng.next_f64() * elites.len() as f64) as usize % elites.len()];
+            let child = ecbf_crossover(a, b, &mut rng);
+            next_gen.push(ecbf_mutate(&child, cfg.mutation_sigma, &mut rng));
+        }
+        population = next_gen;
+    }
+
+    ecbf_decode(base, &best_genes)
+}
+
+fn ecbf_encode(params: &FormationEcbfParams) -> Vec<f64> {
+    ECBF_TUNABLES.iter().map(|(get, _, _, _)| get(params)).collect()
+}
+
+fn ecbf_decode(base: &FormationEcbfParams, genes: &[f64]) -> FormationEcbfParams {
+    let mut params = base.clone();
+    for ((_, set, lo, hi), gene) in ECBF_TUNABLES.iter().zip(genes) {
+        set(&mut params, gene.clamp(*lo, *hi));
+    }
+    params
+}
+
+fn ecbf_mutate(genes: &[f64], sigma: f64, rng: &mut Rng64) -> Vec<f64> {
+    genes
+        .iter()
+        .zip(ECBF_TUNABLES.iter())
+        .map(|(g, (_, _, lo, hi))| {
+            let step = (hi - lo) * sigma;
+            (g + rng.next_gaussian() * step).clamp(*lo, *hi)
+        })
+        .collect()
+}
+
+fn ecbf_crossover(a: &[f64], b: &[f64], rng: &mut Rng64) -> Vec<f64> {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| if rng.next_f64() < 0.5 { *x } else { *y })
+        .collect()
+}
+
+fn ecbf_rollout_fitness(
+    params: &FormationEcbfParams,
+    configs: &[BodyConfig],
+    dt: f64,
+    cfg: &EvolveConfig,
+) -> f64 {
+    let mut model = ParticleModel::new(configs.to_vec(), dt);
+    let mut controller = FormationEcbf::new(params.clone());
+    let steps = cfg.rollout_steps.max(1);
+
+    let mut tracking_total = 0.0;
+    let mut clearance_violation = 0.0;
+    let mut effort_total = 0.0;
+
+    for _ in 0..steps {
+        controller.apply(&mut model, false);
+
+        let positions = model.positions().to_vec();
+        let errors = controller.formation_errors(&positions);
+        tracking_total += errors.iter().sum::<f64>() / errors.len().max(1) as f64;
+
+        let margin = controller.min_obstacle_margin(&positions, model.time());
+        if margin < 0.0 {
+            clearance_violation += -margin;
+        }
+
+        for body in model.body_snapshots() {
+            effort_total += Vector3::new(body.force[0], body.force[1], body.force[2]).norm();
+        }
+
+        model.step();
+    }
+
+    cfg.weight_tracking * (tracking_total / steps as f64)
+        + cfg.weight_clearance * clearance_violation
+        + cfg.weight_effort * (effort_total / steps as f64)
+}
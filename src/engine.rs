@@ -1,6 +1,9 @@
+use crate::algorithms::compute_backend::Backend;
 use crate::algorithms::flocking::{FlockParams, Flocking};
 use crate::algorithms::flocking_alpha::{FlockAlphaParams, FlockingAlpha};
 use crate::algorithms::formation_ecbf::{FormationEcbf, FormationEcbfParams};
+use crate::algorithms::gravity::{Gravity, GravityParams};
+use crate::algorithms::registry::{Algorithm, AlgorithmRegistry};
 use crate::algorithms::safe_flocking_alpha::{SafeFlockAlphaParams, SafeFlockingAlpha};
 use crate::models::particles::{
     lattice_demo_configs, quadrotor_demo_configs, ring_demo_configs, ParticleModel, DEMO_COUNT,
@@ -8,6 +11,7 @@ use crate::models::particles::{
 };
 use crate::BodyConfig;
 use nalgebra::Vector3;
+use std::collections::BTreeMap;
 
 pub const MODEL_RING: &str = "ring-swarm";
 pub const MODEL_LATTICE: &str = "lattice-swarm";
@@ -19,6 +23,7 @@ pub const ALGO_FLOCKING: &str = "flocking";
 pub const ALGO_FLOCKING_ALPHA: &str = "flocking-alpha";
 pub const ALGO_FORMATION_ECBF: &str = "formation-ecbf";
 pub const ALGO_SAFE_FLOCKING_ALPHA: &str = "safe-flocking-alpha";
+pub const ALGO_GRAVITY: &str = "gravity";
 
 pub struct ModelInfo {
     pub id: &'static str,
@@ -27,6 +32,22 @@ pub struct ModelInfo {
     pub default_algorithm: &'static str,
 }
 
+/// Reduction over one group's members: centroid, mean velocity,
+/// axis-aligned bounds, mean speed, and `spread` (RMS distance from the
+/// centroid) — everything a UI needs to draw a per-fleet label/bounding box
+/// without pulling the full state matrix across a binding boundary.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GroupStats {
+    pub id: u32,
+    pub count: usize,
+    pub centroid: [f64; 3],
+    pub mean_velocity: [f64; 3],
+    pub bounding_min: [f64; 3],
+    pub bounding_max: [f64; 3],
+    pub mean_speed: f64,
+    pub spread: f64,
+}
+
 pub struct AlgorithmInfo {
     pub id: &'static str,
     pub name: &'static str,
@@ -100,6 +121,12 @@ pub fn algorithm_catalog() -> &'static [AlgorithmInfo] {
             description: "Alpha-lattice nominal flocking filtered by CBF-QP (obstacles + inter-agent).",
             compatible_models: &[MODEL_RING, MODEL_LATTICE, MODEL_FROM_STATES],
         },
+        AlgorithmInfo {
+            id: ALGO_GRAVITY,
+            name: "Gravity (Barnes-Hut)",
+            description: "Mutual gravitational N-body force, Barnes-Hut approximated for large N.",
+            compatible_models: &[MODEL_RING, MODEL_LATTICE, MODEL_FROM_STATES],
+        },
     ]
 }
 
@@ -107,19 +134,11 @@ enum ModelKind {
     Particles(ParticleModel),
 }
 
-enum AlgorithmKind {
-    None,
-    Flocking(Flocking),
-    FlockingAlpha(FlockingAlpha),
-    FormationEcbf(FormationEcbf),
-    SafeFlockingAlpha(SafeFlockingAlpha),
-}
-
 pub struct Engine {
     model_id: &'static str,
     algorithm_id: &'static str,
     model: ModelKind,
-    algorithm: AlgorithmKind,
+    algorithm: Box<dyn Algorithm>,
     plane_2d: bool,
 }
 
@@ -192,51 +211,12 @@ impl Engine {
     }
 
     pub fn tick(&mut self) {
-        match (&mut self.model, &mut self.algorithm) {
-            (ModelKind::Particles(model), AlgorithmKind::Flocking(algo)) => {
-                if self.plane_2d {
-                    model.flatten_to_plane();
-                }
-                algo.apply(model, self.plane_2d);
-                model.step();
-                if self.plane_2d {
-                    model.flatten_to_plane();
-                }
-            }
-            (ModelKind::Particles(model), AlgorithmKind::FlockingAlpha(algo)) => {
-                if self.plane_2d {
-                    model.flatten_to_plane();
-                }
-                algo.apply(model, self.plane_2d);
-                model.step();
-                if self.plane_2d {
-                    model.flatten_to_plane();
-                }
-            }
-            (ModelKind::Particles(model), AlgorithmKind::FormationEcbf(algo)) => {
-                if self.plane_2d {
-                    model.flatten_to_plane();
-                }
-                algo.apply(model, self.plane_2d);
-                model.step();
-                if self.plane_2d {
-                    model.flatten_to_plane();
-                }
-            }
-            (ModelKind::Particles(model), AlgorithmKind::SafeFlockingAlpha(algo)) => {
-                if self.plane_2d {
-                    model.flatten_to_plane();
-                }
-                algo.apply(model, self.plane_2d);
-                model.step();
-                if self.plane_2d {
-                    model.flatten_to_plane();
-                }
-            }
-            (ModelKind::Particles(model), AlgorithmKind::None) => {
+        match &mut self.model {
+            ModelKind::Particles(model) => {
                 if self.plane_2d {
                     model.flatten_to_plane();
                 }
+                self.algorithm.apply(model, self.plane_2d);
                 model.step();
                 if self.plane_2d {
                     model.flatten_to_plane();
@@ -274,6 +254,28 @@ impl Engine {
         }
     }
 
+    /// Zero-allocation counterpart to `positions_flat`: fills `out` with the
+    /// same `[x,y,z, ...]` layout and returns how many floats were written,
+    /// so a caller can reuse one buffer across frames instead of getting a
+    /// fresh `Vec` every tick. Errors if `out` is smaller than `len() * 3`.
+    pub fn write_positions(&self, out: &mut [f32]) -> Result<usize, String> {
+        match &self.model {
+            ModelKind::Particles(model) => {
+                let positions = model.positions();
+                let needed = positions.len() * 3;
+                if out.len() < needed {
+                    return Err(format!("buffer too small: need {} floats, got {}", needed, out.len()));
+                }
+                for (i, p) in positions.iter().enumerate() {
+                    out[i * 3] = p.x as f32;
+                    out[i * 3 + 1] = p.y as f32;
+                    out[i * 3 + 2] = p.z as f32;
+                }
+                Ok(needed)
+            }
+        }
+    }
+
     pub fn state_matrix_flat(&self) -> Vec<f32> {
         match &self.model {
             ModelKind::Particles(model) => {
@@ -292,6 +294,86 @@ impl Engine {
         }
     }
 
+    /// Zero-allocation counterpart to `state_matrix_flat`. See
+    /// `write_positions` for the buffer-reuse rationale.
+    pub fn write_state_matrix(&self, out: &mut [f32]) -> Result<usize, String> {
+        match &self.model {
+            ModelKind::Particles(model) => {
+                let states = model.state_matrix();
+                let needed = states.len() * 6;
+                if out.len() < needed {
+                    return Err(format!("buffer too small: need {} floats, got {}", needed, out.len()));
+                }
+                for (i, row) in states.iter().enumerate() {
+                    let base = i * 6;
+                    for k in 0..6 {
+                        out[base + k] = row[k] as f32;
+                    }
+                }
+                Ok(needed)
+            }
+        }
+    }
+
+    /// Per-group aggregate stats (centroid, mean velocity, bounds, mean
+    /// speed, RMS spread), one entry per distinct group id in ascending
+    /// order.
+    pub fn group_stats(&self) -> Vec<GroupStats> {
+        match &self.model {
+            ModelKind::Particles(model) => {
+                let states = model.state_matrix();
+                let groups = model.groups();
+
+                let mut by_group: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+                for (i, &g) in groups.iter().enumerate() {
+                    by_group.entry(g).or_default().push(i);
+                }
+
+                let mut out = Vec::with_capacity(by_group.len());
+                for (gid, members) in by_group {
+                    let n = members.len();
+                    let inv = 1.0 / n as f64;
+
+                    let mut centroid = Vector3::zeros();
+                    let mut mean_velocity = Vector3::zeros();
+                    let mut bounding_min = Vector3::repeat(f64::INFINITY);
+                    let mut bounding_max = Vector3::repeat(f64::NEG_INFINITY);
+                    let mut speed_sum = 0.0;
+                    for &i in &members {
+                        let s = states[i];
+                        let pos = Vector3::new(s[0], s[1], s[2]);
+                        let vel = Vector3::new(s[3], s[4], s[5]);
+                        centroid += pos;
+                        mean_velocity += vel;
+                        speed_sum += vel.norm();
+                        bounding_min = bounding_min.zip_map(&pos, f64::min);
+                        bounding_max = bounding_max.zip_map(&pos, f64::max);
+                    }
+                    centroid *= inv;
+                    mean_velocity *= inv;
+
+                    let mut sq_dev_sum = 0.0;
+                    for &i in &members {
+                        let pos = Vector3::new(states[i][0], states[i][1], states[i][2]);
+                        sq_dev_sum += (pos - centroid).norm_squared();
+                    }
+
+                    out.push(GroupStats {
+                        id: gid as u32,
+                        count: n,
+                        centroid: [centroid.x, centroid.y, centroid.z],
+                        mean_velocity: [mean_velocity.x, mean_velocity.y, mean_velocity.z],
+                        bounding_min: [bounding_min.x, bounding_min.y, bounding_min.z],
+                        bounding_max: [bounding_max.x, bounding_max.y, bounding_max.z],
+                        mean_speed: speed_sum * inv,
+                        spread: (sq_dev_sum * inv).sqrt(),
+                    });
+                }
+                out
+            }
+        }
+    }
+
     pub fn dt(&self) -> f64 {
         match &self.model {
             ModelKind::Particles(model) => model.dt(),
@@ -306,6 +388,20 @@ impl Engine {
 
     pub fn set_plane_2d(&mut self, enabled: bool) { self.plane_2d = enabled; }
 
+    pub fn plane_2d(&self) -> bool { self.plane_2d }
+
+    /// Catalog id of the currently active algorithm (one of the `ALGO_*`
+    /// constants).
+    pub fn algorithm_id(&self) -> &'static str { self.algorithm_id }
+
+    /// Per-body mass/state/drag/group/force, for persisting a full snapshot
+    /// of the simulation (see `BodySnapshot`).
+    pub fn body_snapshots(&self) -> Vec<crate::BodySnapshot> {
+        match &self.model {
+            ModelKind::Particles(model) => model.body_snapshots(),
+        }
+    }
+
     pub fn set_algorithm(&mut self, algorithm_id: &str) -> Result<(), String> {
         let algorithm_id = normalize_algorithm_id(algorithm_id)
             .ok_or_else(|| format!("unknown algorithm id '{}'", algorithm_id))?;
@@ -340,64 +436,121 @@ impl Engine {
     }
 
     pub fn set_flock_params(&mut self, params: FlockParams) -> Result<(), String> {
-        match &mut self.algorithm {
-            AlgorithmKind::Flocking(algo) => {
+        match self.algorithm.as_any_mut().downcast_mut::<Flocking>() {
+            Some(algo) => {
                 algo.params = params;
                 Ok(())
             }
-            _ => Err("current algorithm does not support flocking params".to_string()),
+            None => Err("current algorithm does not support flocking params".to_string()),
         }
     }
 
     pub fn set_flock_alpha_params(&mut self, params: FlockAlphaParams) -> Result<(), String> {
-        match &mut self.algorithm {
-            AlgorithmKind::FlockingAlpha(algo) => {
+        match self.algorithm.as_any_mut().downcast_mut::<FlockingAlpha>() {
+            Some(algo) => {
                 algo.params = params;
                 Ok(())
             }
-            _ => Err("current algorithm does not support flocking-alpha params".to_string()),
+            None => Err("current algorithm does not support flocking-alpha params".to_string()),
         }
     }
 
     pub fn set_formation_ecbf_params(&mut self, params: FormationEcbfParams) -> Result<(), String> {
-        match &mut self.algorithm {
-            AlgorithmKind::FormationEcbf(algo) => {
+        match self.algorithm.as_any_mut().downcast_mut::<FormationEcbf>() {
+            Some(algo) => {
                 algo.params = params;
                 Ok(())
             }
-            _ => Err("current algorithm does not support formation-ecbf params".to_string()),
+            None => Err("current algorithm does not support formation-ecbf params".to_string()),
         }
     }
 
     pub fn set_safe_flocking_alpha_params(&mut self, params: SafeFlockAlphaParams) -> Result<(), String> {
-        match &mut self.algorithm {
-            AlgorithmKind::SafeFlockingAlpha(algo) => {
+        match self.algorithm.as_any_mut().downcast_mut::<SafeFlockingAlpha>() {
+            Some(algo) => {
                 algo.params = params;
                 Ok(())
             }
-            _ => Err("current algorithm does not support safe-flocking-alpha params".to_string()),
+            None => Err("current algorithm does not support safe-flocking-alpha params".to_string()),
         }
     }
 
-    pub fn attitudes_flat(&self) -> Vec<f32> {
-        match &self.algorithm {
-            AlgorithmKind::FormationEcbf(algo) => algo.attitudes_flat(),
-            _ => Vec::new(),
+    /// Read back the active algorithm's flocking params, if `Flocking` is
+    /// the current algorithm.
+    pub fn flock_params(&self) -> Option<FlockParams> {
+        self.algorithm.as_any().downcast_ref::<Flocking>().map(|algo| algo.params.clone())
+    }
+
+    /// Read back the active algorithm's flocking-alpha params, if
+    /// `FlockingAlpha` is the current algorithm.
+    pub fn flock_alpha_params(&self) -> Option<FlockAlphaParams> {
+        self.algorithm.as_any().downcast_ref::<FlockingAlpha>().map(|algo| algo.params.clone())
+    }
+
+    /// Read back the active algorithm's formation-ECBF params, if
+    /// `FormationEcbf` is the current algorithm.
+    pub fn formation_ecbf_params(&self) -> Option<FormationEcbfParams> {
+        self.algorithm.as_any().downcast_ref::<FormationEcbf>().map(|algo| algo.params.clone())
+    }
+
+    /// Read back the active algorithm's safe-flocking-alpha params, if
+    /// `SafeFlockingAlpha` is the current algorithm.
+    pub fn safe_flocking_alpha_params(&self) -> Option<SafeFlockAlphaParams> {
+        self.algorithm.as_any().downcast_ref::<SafeFlockingAlpha>().map(|algo| algo.params.clone())
+    }
+
+    pub fn set_gravity_params(&mut self, params: GravityParams) -> Result<(), String> {
+        match self.algorithm.as_any_mut().downcast_mut::<Gravity>() {
+            Some(algo) => {
+                algo.params = params;
+                Ok(())
+            }
+            None => Err("current algorithm does not support gravity params".to_string()),
         }
     }
 
-    pub fn reset_agent(&mut self, index: usize, pos: Vector3<f64>, vel: Vector3<f64>) {
-        match &mut self.algorithm {
-            AlgorithmKind::FormationEcbf(algo) => algo.reset_agent(index, pos, vel),
-            _ => {}
+    /// Read back the active algorithm's gravity params, if `Gravity` is the
+    /// current algorithm.
+    pub fn gravity_params(&self) -> Option<GravityParams> {
+        self.algorithm.as_any().downcast_ref::<Gravity>().map(|algo| algo.params.clone())
+    }
+
+    /// Select which `ComputeBackend` the active algorithm evaluates its
+    /// pairwise neighbor sums on. Currently only `Flocking` reads this; any
+    /// other active algorithm returns an error, the same way
+    /// `set_flock_params` does for a mismatched algorithm.
+    pub fn set_compute_backend(&mut self, backend: Backend) -> Result<(), String> {
+        match self.algorithm.as_any_mut().downcast_mut::<Flocking>() {
+            Some(algo) => {
+                algo.backend = backend;
+                Ok(())
+            }
+            None => Err("current algorithm does not support a compute backend selector".to_string()),
         }
     }
 
+    pub fn attitudes_flat(&self) -> Vec<f32> {
+        self.algorithm.attitudes_flat()
+    }
+
+    /// Zero-allocation counterpart to `attitudes_flat`. See
+    /// `write_positions` for the buffer-reuse rationale.
+    pub fn write_attitudes(&self, out: &mut [f32]) -> Result<usize, String> {
+        write_into(out, &self.algorithm.attitudes_flat())
+    }
+
+    pub fn reset_agent(&mut self, index: usize, pos: Vector3<f64>, vel: Vector3<f64>) {
+        self.algorithm.reset_agent(index, pos, vel);
+    }
+
     pub fn debug_states_flat(&self) -> Vec<f32> {
-        match (&self.model, &self.algorithm) {
-            (ModelKind::Particles(model), AlgorithmKind::SafeFlockingAlpha(algo)) => {
+        if self.algorithm.id() != ALGO_SAFE_FLOCKING_ALPHA {
+            return self.state_matrix_flat();
+        }
+        match &self.model {
+            ModelKind::Particles(model) => {
                 let states = model.state_matrix();
-                let dbg = algo.debug_flat();
+                let dbg = self.algorithm.debug_flat();
                 let n = states.len();
                 if n == 0 {
                     return Vec::new();
@@ -433,9 +586,25 @@ impl Engine {
                 }
                 out
             }
-            _ => self.state_matrix_flat(),
         }
     }
+
+    /// Zero-allocation counterpart to `debug_states_flat`. See
+    /// `write_positions` for the buffer-reuse rationale.
+    pub fn write_debug_states(&self, out: &mut [f32]) -> Result<usize, String> {
+        write_into(out, &self.debug_states_flat())
+    }
+}
+
+/// Copy `data` into `out`, erroring instead of panicking if `out` is too
+/// small. Shared by the `write_*` zero-allocation counterparts to the
+/// `*_flat` methods.
+fn write_into(out: &mut [f32], data: &[f32]) -> Result<usize, String> {
+    if out.len() < data.len() {
+        return Err(format!("buffer too small: need {} floats, got {}", data.len(), out.len()));
+    }
+    out[..data.len()].copy_from_slice(data);
+    Ok(data.len())
 }
 
 fn normalize_model_id(id: &str) -> Option<&'static str> {
@@ -459,7 +628,7 @@ fn normalize_algorithm_id(id: &str) -> Option<&'static str> {
     }
 }
 
-fn build_algorithm(id: &'static str, model_id: &'static str) -> Result<AlgorithmKind, String> {
+fn build_algorithm(id: &'static str, model_id: &'static str) -> Result<Box<dyn Algorithm>, String> {
     let compatible = algorithm_catalog()
         .iter()
         .find(|a| a.id == id)
@@ -470,20 +639,9 @@ fn build_algorithm(id: &'static str, model_id: &'static str) -> Result<Algorithm
         return Err(format!("algorithm '{}' is not compatible with model '{}'", id, model_id));
     }
 
-    match id {
-        ALGO_NONE => Ok(AlgorithmKind::None),
-        ALGO_FLOCKING => Ok(AlgorithmKind::Flocking(Flocking::new(FlockParams::default()))),
-        ALGO_FLOCKING_ALPHA => Ok(AlgorithmKind::FlockingAlpha(FlockingAlpha::new(
-            FlockAlphaParams::default(),
-        ))),
-        ALGO_FORMATION_ECBF => Ok(AlgorithmKind::FormationEcbf(FormationEcbf::new(
-            FormationEcbfParams::default(),
-        ))),
-        ALGO_SAFE_FLOCKING_ALPHA => Ok(AlgorithmKind::SafeFlockingAlpha(SafeFlockingAlpha::new(
-            SafeFlockAlphaParams::default(),
-        ))),
-        _ => Err(format!("unknown algorithm id '{}'", id)),
-    }
+    AlgorithmRegistry::global()
+        .build(id)
+        .ok_or_else(|| format!("unknown algorithm id '{}'", id))
 }
 
 fn build_model(model_id: &'static str) -> Result<(ModelKind, &'static str), String> {
@@ -0,0 +1,79 @@
+use crate::algorithms::rng::Rng64;
+use crate::engine::Engine;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Snapshots of `state_matrix_flat` taken every `stride` ticks for one
+/// `Engine` in an ensemble, in tick order.
+#[derive(Debug, Clone, Default)]
+pub struct EnsembleTrajectory {
+    pub states: Vec<Vec<f32>>,
+}
+
+/// Output of `run_ensemble`/`run_ensemble_from_factory`: one trajectory per
+/// engine, in the same order the engines were given, plus how long the
+/// whole batch took to advance.
+#[derive(Debug, Clone)]
+pub struct EnsembleResult {
+    pub trajectories: Vec<EnsembleTrajectory>,
+    pub elapsed: Duration,
+}
+
+/// Advance every `Engine` in `engines` by `steps` ticks, snapshotting
+/// `state_matrix_flat` every `snapshot_stride` ticks (clamped to at least
+/// 1). Each engine only mutates its own state, so with the `parallel`
+/// feature the ensemble is fanned out across rayon's thread pool; without
+/// it, engines are rolled out one at a time in order.
+pub fn run_ensemble(mut engines: Vec<Engine>, steps: usize, snapshot_stride: usize) -> EnsembleResult {
+    let stride = snapshot_stride.max(1);
+    let start = Instant::now();
+
+    #[cfg(feature = "parallel")]
+    let trajectories: Vec<EnsembleTrajectory> = engines
+        .par_iter_mut()
+        .map(|engine| rollout_one(engine, steps, stride))
+        .collect();
+
+    #[cfg(not(feature = "parallel"))]
+    let trajectories: Vec<EnsembleTrajectory> = engines
+        .iter_mut()
+        .map(|engine| rollout_one(engine, steps, stride))
+        .collect();
+
+    EnsembleResult {
+        trajectories,
+        elapsed: start.elapsed(),
+    }
+}
+
+/// Build `count` engines from `factory` (called once per engine with a
+/// deterministic per-engine seed derived from `base_seed` via `Rng64`, so
+/// e.g. perturbed initial states or parameter draws stay reproducible)
+/// and roll them all out with `run_ensemble`.
+pub fn run_ensemble_from_factory<F>(
+    factory: F,
+    count: usize,
+    base_seed: u64,
+    steps: usize,
+    snapshot_stride: usize,
+) -> EnsembleResult
+where
+    F: Fn(u64) -> Engine,
+{
+    let mut rng = Rng64::new(base_seed);
+    let engines: Vec<Engine> = (0..count).map(|_| factory(rng.next_u64())).collect();
+    run_ensemble(engines, steps, snapshot_stride)
+}
+
+fn rollout_one(engine: &mut Engine, steps: usize, stride: usize) -> EnsembleTrajectory {
+    let mut states = Vec::with_capacity(steps / stride + 1);
+    for step in 0..steps {
+        engine.tick();
+        if step % stride == 0 {
+            states.push(engine.state_matrix_flat());
+        }
+    }
+    EnsembleTrajectory { states }
+}
@@ -1,21 +1,403 @@
-use rphys::{BodyConfig, Point, Simulator};
+use crate::algorithms::rng::Rng64;
+use crate::algorithms::spatial_grid::SpatialGrid;
+use crate::models::particles::ParticleModel;
+use nalgebra::Vector3;
 
-pub enum FlockAlgorithm {
-    Basic,
-    WithGamma,
-    WithObstacles,
+/// Static spherical obstacle: center + radius.
+#[derive(Debug, Clone, Copy)]
+pub struct Obstacle {
+    pub center: Vector3<f64>,
+    pub radius: f64,
 }
 
+/// Read-only view handed to a `BoidRule` for one body's evaluation.
+pub struct RuleContext<'a> {
+    pub index: usize,
+    pub position: Vector3<f64>,
+    pub velocity: Vector3<f64>,
+    pub neighbors: &'a [usize],
+    pub positions: &'a [Vector3<f64>],
+    pub velocities: &'a [Vector3<f64>],
+    /// Per-body group id (from `BodyConfig`/`to_body_config`), indexed the
+    /// same as `positions`/`velocities`. Lets rules like `FleePredator`
+    /// implement friend/enemy relations instead of treating every neighbor
+    /// the same.
+    pub groups: &'a [usize],
+    pub eps: f64,
+}
+
+/// One behavior in the rule stack. Returns a desired acceleration plus how
+/// satisfied the rule already is (`0` = fully unmet, `1` = fully met), so the
+/// `Fuzzy` evaluation mode can decide whether to keep consulting lower-
+/// priority rules.
+pub trait BoidRule {
+    fn evaluate(&self, ctx: &RuleContext) -> (Vector3<f64>, f64);
+    fn gain(&self) -> f64 {
+        1.0
+    }
+}
+
+pub struct Separation {
+    pub radius: f64,
+    pub gain: f64,
+}
+
+impl BoidRule for Separation {
+    fn evaluate(&self, ctx: &RuleContext) -> (Vector3<f64>, f64) {
+        let mut push = Vector3::new(0.0, 0.0, 0.0);
+        let mut close = 0usize;
+        for &j in ctx.neighbors {
+            let diff = ctx.position - ctx.positions[j];
+            let dist = diff.norm();
+            if dist < self.radius && dist > ctx.eps {
+                push += diff / dist * (self.radius - dist);
+                close += 1;
+            }
+        }
+        let satisfaction = if close == 0 { 1.0 } else { 0.0 };
+        (push * self.gain, satisfaction)
+    }
+
+    fn gain(&self) -> f64 {
+        self.gain
+    }
+}
+
+pub struct Cohesion {
+    pub gain: f64,
+}
+
+impl BoidRule for Cohesion {
+    fn evaluate(&self, ctx: &RuleContext) -> (Vector3<f64>, f64) {
+        if ctx.neighbors.is_empty() {
+            return (Vector3::new(0.0, 0.0, 0.0), 1.0);
+        }
+        let mut center = Vector3::new(0.0, 0.0, 0.0);
+        for &j in ctx.neighbors {
+            center += ctx.positions[j];
+        }
+        center /= ctx.neighbors.len() as f64;
+        let to_center = center - ctx.position;
+        let satisfaction = 1.0 / (1.0 + to_center.norm());
+        (to_center * self.gain, satisfaction)
+    }
+
+    fn gain(&self) -> f64 {
+        self.gain
+    }
+}
+
+pub struct Alignment {
+    pub gain: f64,
+}
+
+impl BoidRule for Alignment {
+    fn evaluate(&self, ctx: &RuleContext) -> (Vector3<f64>, f64) {
+        if ctx.neighbors.is_empty() {
+            return (Vector3::new(0.0, 0.0, 0.0), 1.0);
+        }
+        let mut avg_vel = Vector3::new(0.0, 0.0, 0.0);
+        for &j in ctx.neighbors {
+            avg_vel += ctx.velocities[j];
+        }
+        avg_vel /= ctx.neighbors.len() as f64;
+        let diff = avg_vel - ctx.velocity;
+        let satisfaction = 1.0 / (1.0 + diff.norm());
+        (diff * self.gain, satisfaction)
+    }
+
+    fn gain(&self) -> f64 {
+        self.gain
+    }
+}
+
+pub struct GoalSeek {
+    pub target: Vector3<f64>,
+    pub gain: f64,
+}
+
+impl BoidRule for GoalSeek {
+    fn evaluate(&self, ctx: &RuleContext) -> (Vector3<f64>, f64) {
+        let to_goal = self.target - ctx.position;
+        let dist = to_goal.norm();
+        let satisfaction = 1.0 / (1.0 + dist);
+        (to_goal * self.gain, satisfaction)
+    }
+
+    fn gain(&self) -> f64 {
+        self.gain
+    }
+}
+
+pub struct ObstacleAvoid {
+    pub obstacles: Vec<Obstacle>,
+    pub look_radius: f64,
+    pub gain: f64,
+}
+
+impl BoidRule for ObstacleAvoid {
+    fn evaluate(&self, ctx: &RuleContext) -> (Vector3<f64>, f64) {
+        let mut push = Vector3::new(0.0, 0.0, 0.0);
+        let mut threatened = 0usize;
+        for obstacle in &self.obstacles {
+            let diff = ctx.position - obstacle.center;
+            let dist = diff.norm();
+            let clearance = dist - obstacle.radius;
+            if clearance < self.look_radius {
+                let dir = diff / dist.max(ctx.eps);
+                push += dir * (self.look_radius - clearance).max(0.0);
+                threatened += 1;
+            }
+        }
+        let satisfaction = if threatened == 0 { 1.0 } else { 0.0 };
+        (push * self.gain, satisfaction)
+    }
+
+    fn gain(&self) -> f64 {
+        self.gain
+    }
+}
+
+pub struct FollowLeader {
+    pub leader: usize,
+    pub offset: Vector3<f64>,
+    pub gain: f64,
+}
+
+impl BoidRule for FollowLeader {
+    fn evaluate(&self, ctx: &RuleContext) -> (Vector3<f64>, f64) {
+        if self.leader >= ctx.positions.len() || self.leader == ctx.index {
+            return (Vector3::new(0.0, 0.0, 0.0), 1.0);
+        }
+        let target = ctx.positions[self.leader] + self.offset;
+        let to_target = target - ctx.position;
+        let satisfaction = 1.0 / (1.0 + to_target.norm());
+        (to_target * self.gain, satisfaction)
+    }
+
+    fn gain(&self) -> f64 {
+        self.gain
+    }
+}
+
+/// Flee any neighbor in `predator_group`: pushes away from every such
+/// neighbor within `look_radius`, each scaled by closeness like `Separation`.
+pub struct FleePredator {
+    pub predator_group: usize,
+    pub look_radius: f64,
+    pub gain: f64,
+}
+
+impl BoidRule for FleePredator {
+    fn evaluate(&self, ctx: &RuleContext) -> (Vector3<f64>, f64) {
+        let mut push = Vector3::new(0.0, 0.0, 0.0);
+        let mut threatened = 0usize;
+        for &j in ctx.neighbors {
+            if ctx.groups.get(j).copied() != Some(self.predator_group) {
+                continue;
+            }
+            let diff = ctx.position - ctx.positions[j];
+            let dist = diff.norm();
+            if dist < self.look_radius && dist > ctx.eps {
+                push += diff / dist * (self.look_radius - dist);
+                threatened += 1;
+            }
+        }
+        let satisfaction = if threatened == 0 { 1.0 } else { 0.0 };
+        (push * self.gain, satisfaction)
+    }
+
+    fn gain(&self) -> f64 {
+        self.gain
+    }
+}
+
+/// How a `RuleStack` combines the outputs of its ordered rules for one body.
+pub enum EvalMode {
+    /// Walk rules in priority order, accumulating contributions until the
+    /// summed *effort* (`1.0 - satisfaction`, i.e. how hard an active rule is
+    /// steering) reaches a budget of 1.0, then stop. An idle rule (`satisfaction
+    /// == 1.0`) costs nothing and lets the walk fall through to the next rule,
+    /// so a high-priority rule only suppresses lower-priority ones while it's
+    /// actually active.
+    Fuzzy,
+    /// Blend every rule's output, weighted by its *effort* (`1.0 -
+    /// satisfaction`) — an idle rule (`satisfaction == 1.0`) contributes
+    /// nothing to the blend, while an actively-steering rule (`satisfaction
+    /// == 0.0`) gets full weight. Each rule's own `evaluate` already bakes
+    /// its gain into the returned vector, so the blend doesn't re-apply it.
+    Average,
+    /// Pick a single rule stochastically, with probability proportional to
+    /// its gain.
+    Random,
+}
+
+/// A point effector: positive `strength` pulls the flock toward `position`
+/// like a goal, negative `strength` makes it a predator the flock flees.
+/// Only bodies within `falloff_radius` feel the effector.
+#[derive(Debug, Clone, Copy)]
+pub struct Effector {
+    pub position: Vector3<f64>,
+    pub strength: f64,
+    pub falloff_radius: f64,
+}
+
+/// Ordered list of boid rules plus a neighbor radius and evaluation mode.
+/// Replaces the old fixed `Basic`/`WithGamma`/`WithObstacles` enum: those
+/// presets are now just specific rule lists (see `FlockingBehavior::basic`,
+/// `with_gamma`, `with_obstacles`).
 pub struct FlockingBehavior {
-    pub algorithm: FlockAlgorithm,
+    pub rules: Vec<Box<dyn BoidRule>>,
+    pub eval_mode: EvalMode,
+    pub neighbor_radius: f64,
     pub eps: f64,
-    pub r: f64,
-    pub c_alpha1: f64,
-    pub c_alpha2: f64,
-    pub c_beta1: f64,
-    pub c_beta2: f64,
-    pub c_gamma1: f64,
-    pub c_gamma2: f64,
-    pub gamma: Option<(Vector3<f64>, Vector3<f64>)>,
-    pub obstacles: Vec<Obstacle>, // center+radius
+    /// Goals (positive strength) and predators (negative strength) whose
+    /// force is folded in alongside the rule-stack output every `apply`.
+    pub effectors: Vec<Effector>,
+    rng: Rng64,
+}
+
+impl FlockingBehavior {
+    pub fn new(rules: Vec<Box<dyn BoidRule>>, eval_mode: EvalMode, neighbor_radius: f64) -> Self {
+        Self {
+            rules,
+            eval_mode,
+            neighbor_radius,
+            eps: 1.0e-6,
+            effectors: Vec::new(),
+            rng: Rng64::new(0xF10C_1000),
+        }
+    }
+
+    /// Net force an agent at `position` feels from every configured effector.
+    /// Goals pull along `strength / distance^2`; predators push the same way
+    /// with a negative `strength`. Distance is clamped near the point so the
+    /// force stays bounded instead of blowing up at zero separation.
+    fn effector_force(&self, position: Vector3<f64>) -> Vector3<f64> {
+        let mut force = Vector3::new(0.0, 0.0, 0.0);
+        for effector in &self.effectors {
+            let diff = effector.position - position;
+            let dist = diff.norm();
+            if dist > effector.falloff_radius {
+                continue;
+            }
+            let dist_clamped = dist.max(0.25);
+            let magnitude = effector.strength / (dist_clamped * dist_clamped);
+            let dir = if dist > self.eps { diff / dist } else { Vector3::new(0.0, 0.0, 0.0) };
+            force += dir * magnitude;
+        }
+        force
+    }
+
+    /// Equivalent of the old `FlockAlgorithm::Basic`: cohesion + alignment + separation.
+    pub fn basic(neighbor_radius: f64, separation_radius: f64) -> Self {
+        Self::new(
+            vec![
+                Box::new(Separation { radius: separation_radius, gain: 1.2 }),
+                Box::new(Cohesion { gain: 0.45 }),
+                Box::new(Alignment { gain: 0.65 }),
+            ],
+            EvalMode::Average,
+            neighbor_radius,
+        )
+    }
+
+    /// Equivalent of `FlockAlgorithm::WithGamma`: `basic` plus a goal term.
+    pub fn with_gamma(neighbor_radius: f64, separation_radius: f64, target: Vector3<f64>) -> Self {
+        let mut behavior = Self::basic(neighbor_radius, separation_radius);
+        behavior.rules.push(Box::new(GoalSeek { target, gain: 0.3 }));
+        behavior
+    }
+
+    /// Equivalent of `FlockAlgorithm::WithObstacles`: `with_gamma` plus avoidance,
+    /// evaluated in `Fuzzy` priority order so avoidance always wins when active.
+    pub fn with_obstacles(
+        neighbor_radius: f64,
+        separation_radius: f64,
+        target: Vector3<f64>,
+        obstacles: Vec<Obstacle>,
+    ) -> Self {
+        Self::new(
+            vec![
+                Box::new(ObstacleAvoid { obstacles, look_radius: neighbor_radius, gain: 2.0 }),
+                Box::new(Separation { radius: separation_radius, gain: 1.2 }),
+                Box::new(Cohesion { gain: 0.45 }),
+                Box::new(Alignment { gain: 0.65 }),
+                Box::new(GoalSeek { target, gain: 0.3 }),
+            ],
+            EvalMode::Fuzzy,
+            neighbor_radius,
+        )
+    }
+
+    /// Apply the configured rule stack to every body in `model`. Neighbor
+    /// lookups are routed through a `SpatialGrid` rebuilt each call.
+    pub fn apply(&mut self, model: &mut ParticleModel) {
+        let positions = model.positions().to_vec();
+        let velocities = model.velocities().to_vec();
+        let groups = model.groups().to_vec();
+        let n = positions.len();
+        if n == 0 || self.rules.is_empty() {
+            return;
+        }
+
+        let grid = SpatialGrid::build(&positions, self.neighbor_radius.max(1.0e-6));
+        let mut forces = Vec::with_capacity(n);
+
+        for i in 0..n {
+            let neighbors = grid.neighbors(i, &positions, self.neighbor_radius);
+            let ctx = RuleContext {
+                index: i,
+                position: positions[i],
+                velocity: velocities[i],
+                neighbors: &neighbors,
+                positions: &positions,
+                velocities: &velocities,
+                groups: &groups,
+                eps: self.eps,
+            };
+
+            let force = match self.eval_mode {
+                EvalMode::Fuzzy => {
+                    let mut acc = Vector3::new(0.0, 0.0, 0.0);
+                    let mut effort = 0.0;
+                    for rule in &self.rules {
+                        if effort >= 1.0 {
+                            break;
+                        }
+                        let (contrib, satisfaction) = rule.evaluate(&ctx);
+                        acc += contrib;
+                        effort += (1.0 - satisfaction).clamp(0.0, 1.0);
+                    }
+                    acc
+                }
+                EvalMode::Average => {
+                    let mut acc = Vector3::new(0.0, 0.0, 0.0);
+                    let mut total_weight = 0.0;
+                    for rule in &self.rules {
+                        let (contrib, satisfaction) = rule.evaluate(&ctx);
+                        let weight = (1.0 - satisfaction).clamp(0.0, 1.0);
+                        acc += contrib * weight;
+                        total_weight += weight;
+                    }
+                    if total_weight > 0.0 {
+                        acc / total_weight
+                    } else {
+                        acc
+                    }
+                }
+                EvalMode::Random => {
+                    let gains: Vec<f64> = self.rules.iter().map(|r| r.gain().abs()).collect();
+                    let pick = self.rng.weighted_index(&gains);
+                    self.rules[pick].evaluate(&ctx).0
+                }
+            };
+
+            forces.push(force + self.effector_force(positions[i]));
+        }
+
+        for (i, f) in forces.into_iter().enumerate() {
+            model.set_force(i, f);
+        }
+    }
 }
@@ -113,6 +113,8 @@ impl Point {
     pub fn trajectory_write(&self) -> bool { self.trajectory_write }
 
     /// Преобразовать точку в конфигурацию для симулятора с указанной группой.
+    /// `Point` не несёт вращательного состояния, поэтому ориентация и
+    /// угловая скорость заданы по умолчанию (см. `BodyConfig::new`).
     pub fn to_body_config(&self, group: usize) -> BodyConfig {
         BodyConfig {
             mass: self.mass,
@@ -120,6 +122,10 @@ impl Point {
             drag_coefficient: self.drag_coefficient,
             trajectory_write: self.trajectory_write,
             group,
+            orientation: sim::IDENTITY_ORIENTATION,
+            angular_velocity: [0.0, 0.0, 0.0],
+            inertia: sim::UNIT_INERTIA,
+            radius: sim::DEFAULT_RADIUS,
         }
     }
 }
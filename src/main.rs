@@ -32,8 +32,8 @@ fn main() {
     // Демонстрация группового симулятора на 2 телах
     let mut sim = Simulator::new(
         &[
-            BodyConfig { mass: 1.0, state: [0.0, 0.0, 0.0, 1.0, 0.0, 0.0], drag_coefficient: 0.0, trajectory_write: true, group: 0 },
-            BodyConfig { mass: 2.0, state: [0.0, 0.0, 0.0, 0.0, 0.0, 0.0], drag_coefficient: 0.0, trajectory_write: false, group: 0 },
+            BodyConfig { trajectory_write: true, ..BodyConfig::new(1.0, [0.0, 0.0, 0.0, 1.0, 0.0, 0.0]) },
+            BodyConfig::new(2.0, [0.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
         ],
         0.01,
     );
@@ -1,3 +1,4 @@
+use crate::algorithms::obstacles::ObstaclePoly;
 use crate::{BodyConfig, Simulator};
 use nalgebra::Vector3;
 use std::f64::consts::PI;
@@ -28,12 +29,19 @@ impl ParticleModel {
 
     pub fn dt(&self) -> f64 { self.sim.dt() }
 
+    pub fn time(&self) -> f64 { self.sim.time() }
+
     pub fn set_dt(&mut self, dt: f64) { self.sim.set_dt(dt); }
 
+    #[cfg(feature = "parallel")]
+    pub fn set_threads(&mut self, n: usize) { self.sim.set_threads(n); }
+
     pub fn positions(&self) -> &[Vector3<f64>] { self.sim.positions() }
 
     pub fn velocities(&self) -> &[Vector3<f64>] { self.sim.velocities() }
 
+    pub fn masses(&self) -> &[f64] { self.sim.masses() }
+
     pub fn set_position(&mut self, i: usize, pos: Vector3<f64>) { self.sim.set_position(i, pos); }
 
     pub fn set_velocity(&mut self, i: usize, vel: Vector3<f64>) { self.sim.set_velocity(i, vel); }
@@ -52,6 +60,65 @@ impl ParticleModel {
 
     pub fn state_matrix(&self) -> Vec<[f64; 6]> { self.sim.state_matrix() }
 
+    pub fn body_snapshots(&self) -> Vec<crate::BodySnapshot> { self.sim.body_snapshots() }
+
+    pub fn set_radius(&mut self, i: usize, radius: f64) { self.sim.set_radius(i, radius); }
+
+    pub fn set_restitution(&mut self, e: f64) { self.sim.set_restitution(e); }
+
+    pub fn set_auto_resolve_collisions(&mut self, enabled: bool) { self.sim.set_auto_resolve_collisions(enabled); }
+
+    /// Resolve pairwise sphere-body overlaps once, using each body's radius
+    /// and the configured restitution. See `Simulator::resolve_collisions`.
+    pub fn resolve_collisions(&mut self) { self.sim.resolve_collisions(); }
+
+    /// One step, but clamped to the earliest time-of-impact against a moving
+    /// `ObstaclePoly` if that impact would otherwise happen mid-step. Steps
+    /// up to the impact, removes the colliding body's inward velocity
+    /// component along the contact normal, then steps the remainder of `dt` — avoiding
+    /// the tunneling a plain `step()` can produce at high relative speed.
+    pub fn step_with_obstacle_collisions(&mut self, obstacles: &[ObstaclePoly], radius: f64) {
+        let full_dt = self.dt();
+        if full_dt <= 0.0 || obstacles.is_empty() {
+            self.step();
+            return;
+        }
+        let t0 = self.time();
+        let positions = self.positions().to_vec();
+        let velocities = self.velocities().to_vec();
+
+        let mut earliest: Option<(f64, usize, Vector3<f64>)> = None;
+        for (i, (pos, vel)) in positions.iter().zip(velocities.iter()).enumerate() {
+            for obstacle in obstacles {
+                let local = obstacle.shifted(t0);
+                if let Some(toi) = local.time_of_impact(*pos, *vel, radius, full_dt) {
+                    if earliest.map_or(true, |(best, _, _)| toi < best) {
+                        let contact = local.pos(toi);
+                        let hit = *pos + *vel * toi;
+                        let normal = (hit - contact).try_normalize(1.0e-9).unwrap_or(Vector3::new(1.0, 0.0, 0.0));
+                        earliest = Some((toi, i, normal));
+                    }
+                }
+            }
+        }
+
+        match earliest {
+            Some((toi, i, normal)) if toi < full_dt => {
+                self.sim.set_dt(toi.max(0.0));
+                self.sim.step();
+                let v = self.sim.velocities()[i];
+                let vn = v.dot(&normal);
+                if vn < 0.0 {
+                    self.sim.set_velocity(i, v - normal * vn);
+                }
+                self.sim.set_dt(full_dt - toi);
+                self.sim.step();
+                self.sim.set_dt(full_dt);
+            }
+            _ => self.step(),
+        }
+    }
+
     /// Force positions/velocities into the XY plane.
     pub fn flatten_to_plane(&mut self) {
         let positions = self.sim.positions().to_vec();
@@ -88,6 +155,10 @@ pub fn configs_from_states(states: &[f64], drag_coefficient: f64) -> Result<Vec<
             drag_coefficient,
             trajectory_write: false,
             group: 0,
+            orientation: crate::sim::IDENTITY_ORIENTATION,
+            angular_velocity: [0.0, 0.0, 0.0],
+            inertia: crate::sim::UNIT_INERTIA,
+            radius: crate::sim::DEFAULT_RADIUS,
         });
     }
     Ok(configs)
@@ -125,6 +196,10 @@ pub fn ring_demo_configs(count: usize) -> Vec<BodyConfig> {
             drag_coefficient: DEMO_DRAG,
             trajectory_write: false,
             group: 0,
+            orientation: crate::sim::IDENTITY_ORIENTATION,
+            angular_velocity: [0.0, 0.0, 0.0],
+            inertia: crate::sim::UNIT_INERTIA,
+            radius: crate::sim::DEFAULT_RADIUS,
         });
     }
 
@@ -151,6 +226,10 @@ pub fn lattice_demo_configs(side: usize) -> Vec<BodyConfig> {
                     drag_coefficient: 0.02,
                     trajectory_write: false,
                     group: 0,
+                    orientation: crate::sim::IDENTITY_ORIENTATION,
+                    angular_velocity: [0.0, 0.0, 0.0],
+                    inertia: crate::sim::UNIT_INERTIA,
+                    radius: crate::sim::DEFAULT_RADIUS,
                 });
             }
         }
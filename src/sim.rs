@@ -1,4 +1,12 @@
-use nalgebra::Vector3;
+use nalgebra::{Quaternion, UnitQuaternion, Vector3};
+
+/// Identity orientation `[w, x, y, z]`, used as the default for bodies that
+/// don't care about rotation.
+pub const IDENTITY_ORIENTATION: [f64; 4] = [1.0, 0.0, 0.0, 0.0];
+/// Default diagonal inertia tensor for bodies that don't specify one.
+pub const UNIT_INERTIA: [f64; 3] = [1.0, 1.0, 1.0];
+/// Default collision radius for bodies that don't specify one.
+pub const DEFAULT_RADIUS: f64 = 0.5;
 
 /// Начальная конфигурация одного тела для симулятора.
 #[derive(Debug, Clone)]
@@ -11,6 +19,14 @@ pub struct BodyConfig {
     pub trajectory_write: bool,
     /// Номер группы (для удобства массового задания сил)
     pub group: usize,
+    /// Ориентация в виде единичного кватерниона `[w, x, y, z]`.
+    pub orientation: [f64; 4],
+    /// Угловая скорость тела в мировых координатах `[wx, wy, wz]`.
+    pub angular_velocity: [f64; 3],
+    /// Диагональный тензор инерции `[Ix, Iy, Iz]` (в главных осях тела).
+    pub inertia: [f64; 3],
+    /// Радиус тела для сферического столкновения (см. `Simulator::resolve_collisions`).
+    pub radius: f64,
 }
 
 /// Полная информация об одном теле в текущий момент времени.
@@ -22,6 +38,10 @@ pub struct BodySnapshot {
     pub trajectory_write: bool,
     pub group: usize,
     pub force: [f64; 3],
+    pub orientation: [f64; 4],
+    pub angular_velocity: [f64; 3],
+    pub torque: [f64; 3],
+    pub radius: f64,
 }
 
 impl BodyConfig {
@@ -32,6 +52,10 @@ impl BodyConfig {
             drag_coefficient: 0.0,
             trajectory_write: false,
             group: 0,
+            orientation: IDENTITY_ORIENTATION,
+            angular_velocity: [0.0, 0.0, 0.0],
+            inertia: UNIT_INERTIA,
+            radius: DEFAULT_RADIUS,
         }
     }
 }
@@ -63,6 +87,76 @@ where
     }
 }
 
+/// Поле моментов сил для всех тел. Заполняет вектор моментов по индексу
+/// тела, по аналогии с `ForceField` для поступательной динамики.
+pub trait TorqueField: Sync {
+    fn torque_all(
+        &self,
+        t: f64,
+        orientation: &[UnitQuaternion<f64>],
+        angular_velocity: &[Vector3<f64>],
+        out: &mut [Vector3<f64>],
+    );
+}
+
+// Удобство: можно передать замыкание вида Fn(t, orientation, angular_velocity, out)
+impl<F> TorqueField for F
+where
+    F: Fn(f64, &[UnitQuaternion<f64>], &[Vector3<f64>], &mut [Vector3<f64>]) + Sync,
+{
+    fn torque_all(
+        &self,
+        t: f64,
+        orientation: &[UnitQuaternion<f64>],
+        angular_velocity: &[Vector3<f64>],
+        out: &mut [Vector3<f64>],
+    ) {
+        (self)(t, orientation, angular_velocity, out)
+    }
+}
+
+/// Производная вращательного состояния одного тела по уравнению Эйлера
+/// `dw/dt = I^-1 * (tau - w x (I*w))` (диагональный тензор инерции в
+/// главных осях тела) и кинематике кватерниона `dq/dt = 0.5 * q (x) (0, w)`.
+/// `q` не обязан быть единичным на промежуточных стадиях РК4 — нормируется
+/// только итоговое состояние после полного шага.
+fn rigid_body_derivative(
+    q: Quaternion<f64>,
+    omega: Vector3<f64>,
+    inertia: Vector3<f64>,
+    torque: Vector3<f64>,
+) -> (Quaternion<f64>, Vector3<f64>) {
+    let omega_quat = Quaternion::new(0.0, omega.x, omega.y, omega.z);
+    let dq = scale_quat(q * omega_quat, 0.5);
+
+    let iw = Vector3::new(inertia.x * omega.x, inertia.y * omega.y, inertia.z * omega.z);
+    let gyroscopic = omega.cross(&iw);
+    let domega = Vector3::new(
+        (torque.x - gyroscopic.x) / inertia.x,
+        (torque.y - gyroscopic.y) / inertia.y,
+        (torque.z - gyroscopic.z) / inertia.z,
+    );
+
+    (dq, domega)
+}
+
+/// `Quaternion` doesn't implement scalar `Mul`/`Div` (only `UnitQuaternion`
+/// does, and only by other rotations), so RK4's `dt * k` / `sum / 6.0`
+/// combinations go through its public `coords: Vector4<f64>` instead.
+fn scale_quat(q: Quaternion<f64>, s: f64) -> Quaternion<f64> {
+    Quaternion::from_vector(q.coords * s)
+}
+
+/// Cell index of `p` in a uniform grid of side `cell_size`, for
+/// `Simulator::resolve_collisions`'s broad phase.
+fn collision_cell_of(p: Vector3<f64>, cell_size: f64) -> (i32, i32, i32) {
+    (
+        (p.x / cell_size).floor() as i32,
+        (p.y / cell_size).floor() as i32,
+        (p.z / cell_size).floor() as i32,
+    )
+}
+
 /// Групповой симулятор для множества точек с трансляционным состоянием.
 /// Хранит массивы позиций/скоростей (SoA), массы, коэффициенты сопротивления и настройку записи траекторий.
 #[derive(Debug)]
@@ -77,6 +171,27 @@ pub struct Simulator {
     time: f64,                    // глобальное модельное время
     dt: f64,                      // шаг по времени по умолчанию
 
+    // Вращательное состояние (опционально используется: тела без моментов
+    // сил просто остаются с единичной ориентацией и нулевой угловой
+    // скоростью).
+    q: Vec<UnitQuaternion<f64>>,   // ориентация
+    omega: Vec<Vector3<f64>>,     // угловая скорость
+    inertia: Vec<Vector3<f64>>,   // диагональный тензор инерции
+    torques: Vec<Vector3<f64>>,   // моменты сил-текущая фаза
+
+    // Столкновения (см. resolve_collisions).
+    radii: Vec<f64>,                    // радиус тела для broad-phase/impulse
+    restitution: f64,                   // коэффициент восстановления e
+    auto_resolve_collisions: bool,      // вызывать ли resolve_collisions() в конце каждого step*
+
+    // Рабочие буферы для РК4 вращательного шага.
+    rq1: Vec<Quaternion<f64>>, rw1: Vec<Vector3<f64>>,
+    rq2: Vec<Quaternion<f64>>, rw2: Vec<Vector3<f64>>,
+    rq3: Vec<Quaternion<f64>>, rw3: Vec<Vector3<f64>>,
+    rq4: Vec<Quaternion<f64>>, rw4: Vec<Vector3<f64>>,
+    tmp_q: Vec<Quaternion<f64>>, tmp_w: Vec<Vector3<f64>>,
+    tmp_q_unit: Vec<UnitQuaternion<f64>>,
+
     // Рабочие буферы для RK4, чтобы не аллоцировать на каждом шаге
     k1x: Vec<Vector3<f64>>, k1v: Vec<Vector3<f64>>,
     k2x: Vec<Vector3<f64>>, k2v: Vec<Vector3<f64>>,
@@ -84,6 +199,22 @@ pub struct Simulator {
     k4x: Vec<Vector3<f64>>, k4v: Vec<Vector3<f64>>,
     tmp_x: Vec<Vector3<f64>>, tmp_v: Vec<Vector3<f64>>,
     fx: Vec<Vector3<f64>>,       // вектор сил-текущая фаза
+
+    // Буферы для семи стадий Dormand-Prince RK45 (step_adaptive_with_field).
+    dp_kx: [Vec<Vector3<f64>>; 7], dp_kv: [Vec<Vector3<f64>>; 7],
+    dp_tmp_x: Vec<Vector3<f64>>, dp_tmp_v: Vec<Vector3<f64>>,
+    dp_next_x: Vec<Vector3<f64>>, dp_next_v: Vec<Vector3<f64>>,
+    /// Relative/absolute tolerance and step-size bounds for the adaptive
+    /// embedded RK45 integrator.
+    pub rtol: f64,
+    pub atol: f64,
+    pub dt_min: f64,
+    pub dt_max: f64,
+
+    // Optional dedicated rayon pool for step_par_with_field; None runs on
+    // whichever pool the caller's thread belongs to (usually the global one).
+    #[cfg(feature = "parallel")]
+    thread_pool: Option<std::sync::Arc<rayon::ThreadPool>>,
 }
 
 impl Simulator {
@@ -96,6 +227,10 @@ impl Simulator {
         let mut groups = Vec::with_capacity(n);
         let mut forces = Vec::with_capacity(n);
         let mut traj = Vec::with_capacity(n);
+        let mut q = Vec::with_capacity(n);
+        let mut omega = Vec::with_capacity(n);
+        let mut inertia = Vec::with_capacity(n);
+        let mut radii = Vec::with_capacity(n);
 
         for c in configs {
             x.push(Vector3::new(c.state[0], c.state[1], c.state[2]));
@@ -105,10 +240,21 @@ impl Simulator {
             groups.push(c.group);
             forces.push(Vector3::new(0.0, 0.0, 0.0));
             traj.push(if c.trajectory_write { Some(Vec::new()) } else { None });
+            let oq = Quaternion::new(
+                c.orientation[0], c.orientation[1], c.orientation[2], c.orientation[3],
+            );
+            q.push(UnitQuaternion::new_normalize(oq));
+            omega.push(Vector3::new(
+                c.angular_velocity[0], c.angular_velocity[1], c.angular_velocity[2],
+            ));
+            inertia.push(Vector3::new(c.inertia[0], c.inertia[1], c.inertia[2]));
+            radii.push(c.radius);
         }
 
         let zero = || vec![Vector3::new(0.0, 0.0, 0.0); n];
         let tmp = || vec![Vector3::new(0.0, 0.0, 0.0); n];
+        let zero_quat = || vec![Quaternion::new(0.0, 0.0, 0.0, 0.0); n];
+        let identity_unit_quat = || vec![UnitQuaternion::identity(); n];
 
         Self {
             x,
@@ -120,12 +266,45 @@ impl Simulator {
             traj,
             time: 0.0,
             dt,
+            q,
+            omega,
+            inertia,
+            torques: zero(),
+            radii,
+            restitution: 0.5,
+            auto_resolve_collisions: false,
+            rq1: zero_quat(), rw1: zero(),
+            rq2: zero_quat(), rw2: zero(),
+            rq3: zero_quat(), rw3: zero(),
+            rq4: zero_quat(), rw4: zero(),
+            tmp_q: zero_quat(), tmp_w: zero(),
+            tmp_q_unit: identity_unit_quat(),
             k1x: zero(), k1v: zero(),
             k2x: zero(), k2v: zero(),
             k3x: zero(), k3v: zero(),
             k4x: zero(), k4v: zero(),
             tmp_x: tmp(), tmp_v: tmp(),
             fx: vec![Vector3::new(0.0, 0.0, 0.0); n],
+            dp_kx: std::array::from_fn(|_| zero()),
+            dp_kv: std::array::from_fn(|_| zero()),
+            dp_tmp_x: tmp(), dp_tmp_v: tmp(),
+            dp_next_x: tmp(), dp_next_v: tmp(),
+            rtol: 1.0e-6,
+            atol: 1.0e-9,
+            dt_min: 1.0e-6,
+            dt_max: dt.max(1.0e-6) * 10.0,
+            #[cfg(feature = "parallel")]
+            thread_pool: None,
+        }
+    }
+
+    /// Dedicate a `n`-thread rayon pool to `step_par_with_field`, instead of
+    /// running on whatever pool the caller happens to be inside. Silently
+    /// keeps the previous pool (or the ambient one) if the pool fails to build.
+    #[cfg(feature = "parallel")]
+    pub fn set_threads(&mut self, n: usize) {
+        if let Ok(pool) = rayon::ThreadPoolBuilder::new().num_threads(n).build() {
+            self.thread_pool = Some(std::sync::Arc::new(pool));
         }
     }
 
@@ -153,6 +332,8 @@ impl Simulator {
 
     pub(crate) fn velocities(&self) -> &[Vector3<f64>] { &self.v }
 
+    pub(crate) fn masses(&self) -> &[f64] { &self.mass }
+
     pub(crate) fn set_position(&mut self, i: usize, pos: Vector3<f64>) { self.x[i] = pos; }
 
     pub(crate) fn set_velocity(&mut self, i: usize, vel: Vector3<f64>) { self.v[i] = vel; }
@@ -177,6 +358,12 @@ impl Simulator {
                 trajectory_write: self.traj[i].is_some(),
                 group: self.groups[i],
                 force: [self.forces[i].x, self.forces[i].y, self.forces[i].z],
+                orientation: [
+                    self.q[i].w, self.q[i].i, self.q[i].j, self.q[i].k,
+                ],
+                angular_velocity: [self.omega[i].x, self.omega[i].y, self.omega[i].z],
+                torque: [self.torques[i].x, self.torques[i].y, self.torques[i].z],
+                radius: self.radii[i],
             });
         }
         out
@@ -194,6 +381,111 @@ impl Simulator {
         }
     }
 
+    /// Радиус тела i для сферического столкновения.
+    pub fn radius(&self, i: usize) -> f64 { self.radii[i] }
+
+    /// Назначить радиус тела i.
+    pub fn set_radius(&mut self, i: usize, radius: f64) { self.radii[i] = radius; }
+
+    /// Коэффициент восстановления `e` для `resolve_collisions` (0 — полностью
+    /// неупругий удар, 1 — абсолютно упругий).
+    pub fn restitution(&self) -> f64 { self.restitution }
+
+    pub fn set_restitution(&mut self, e: f64) { self.restitution = e; }
+
+    /// Если true, каждый `step*` вызывает `resolve_collisions()` сразу после
+    /// интегрирования — удобно для гранулярных/упакованных сцен, где
+    /// столкновения нужны каждый шаг без ручного вызова.
+    pub fn set_auto_resolve_collisions(&mut self, enabled: bool) { self.auto_resolve_collisions = enabled; }
+
+    pub fn auto_resolve_collisions(&self) -> bool { self.auto_resolve_collisions }
+
+    /// Разрешить парные столкновения сфер: широкая фаза — равномерная сетка
+    /// с ячейкой `max_diameter = 2 * max(radii)`, проверяются только пары из
+    /// одной или соседних ячеек. Для каждой перекрывающейся пары (`overlap =
+    /// r_i + r_j - |x_i - x_j| > 0`) тела раздвигаются вдоль нормали контакта
+    /// пропорционально обратной массе (позиционная коррекция), а скорости
+    /// обновляются импульсом `j = -(1+e)*(v_rel . n) / (1/m_i + 1/m_j)` вдоль
+    /// той же нормали. Можно вызывать вручную между шагами или включить
+    /// автоматически через `set_auto_resolve_collisions`.
+    pub fn resolve_collisions(&mut self) {
+        let n = self.len();
+        if n == 0 {
+            return;
+        }
+
+        let max_diameter = self.radii.iter().cloned().fold(0.0_f64, f64::max) * 2.0;
+        let cell_size = if max_diameter.is_finite() && max_diameter > 0.0 {
+            max_diameter
+        } else {
+            1.0
+        };
+
+        let mut cells: std::collections::HashMap<(i32, i32, i32), Vec<usize>> =
+            std::collections::HashMap::with_capacity(n);
+        for i in 0..n {
+            cells.entry(collision_cell_of(self.x[i], cell_size)).or_default().push(i);
+        }
+
+        for i in 0..n {
+            let (cx, cy, cz) = collision_cell_of(self.x[i], cell_size);
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    for dz in -1..=1 {
+                        let Some(bucket) = cells.get(&(cx + dx, cy + dy, cz + dz)) else {
+                            continue;
+                        };
+                        for &j in bucket {
+                            if j <= i {
+                                continue;
+                            }
+                            self.resolve_pair(i, j);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn resolve_pair(&mut self, i: usize, j: usize) {
+        let diff = self.x[i] - self.x[j];
+        let dist = diff.norm();
+        let overlap = self.radii[i] + self.radii[j] - dist;
+        if overlap <= 0.0 {
+            return;
+        }
+        let normal = if dist > 1.0e-9 {
+            diff / dist
+        } else {
+            Vector3::new(1.0, 0.0, 0.0)
+        };
+
+        let inv_mi = 1.0 / self.mass[i];
+        let inv_mj = 1.0 / self.mass[j];
+        let inv_sum = inv_mi + inv_mj;
+        if inv_sum <= 0.0 {
+            return;
+        }
+
+        let correction = normal * (overlap / inv_sum);
+        self.x[i] += correction * inv_mi;
+        self.x[j] -= correction * inv_mj;
+
+        let rel_vel = self.v[i] - self.v[j];
+        let vn = rel_vel.dot(&normal);
+        if vn < 0.0 {
+            let impulse = -(1.0 + self.restitution) * vn / inv_sum;
+            self.v[i] += normal * (impulse * inv_mi);
+            self.v[j] -= normal * (impulse * inv_mj);
+        }
+    }
+
+    /// Текущая ориентация тел (единичные кватернионы).
+    pub(crate) fn orientations(&self) -> &[UnitQuaternion<f64>] { &self.q }
+
+    /// Текущие угловые скорости тел.
+    pub(crate) fn angular_velocities(&self) -> &[Vector3<f64>] { &self.omega }
+
     /// Один шаг RK4, используя сохранённые в self.forces (константные во времени на шаг) внешние силы.
     pub fn step(&mut self) {
         let n = self.len();
@@ -256,6 +548,10 @@ impl Simulator {
                 ]);
             }
         }
+
+        if self.auto_resolve_collisions {
+            self.resolve_collisions();
+        }
     }
 
     /// Один шаг RK4 с внешним полем сил field.
@@ -320,11 +616,299 @@ impl Simulator {
                 ]);
             }
         }
+
+        if self.auto_resolve_collisions {
+            self.resolve_collisions();
+        }
     }
 
-    /// Параллельный шаг RK4 (только если включена фича parallel).
+    /// One adaptive step with the Dormand-Prince RK5(4) embedded pair:
+    /// evaluates the standard seven-stage tableau, forms both the 5th-order
+    /// solution and the 4th-order embedded one, and uses their difference
+    /// (scaled by `atol + rtol*max(|state|, |state_next|)` per component) as
+    /// a local error estimate. Accepts the step and advances time/trajectory
+    /// when the RMS error is `<= 1`, otherwise shrinks `dt` and retries
+    /// without advancing; after `MAX_REJECTIONS` (25) retries the step is
+    /// accepted whatever its error. Either way `self.dt` is left at the step-size
+    /// I-only controller's suggestion (`scale = SAFETY * err^(-1/5)`, no
+    /// proportional/previous-error term) for the *next* call; returns the
+    /// `dt` that was actually taken (useful for a variable-time-step caller
+    /// loop).
+    pub fn step_adaptive_with_field<F: ForceField>(&mut self, field: &F) -> f64 {
+        let n = self.len();
+        if n == 0 {
+            return self.dt;
+        }
+
+        const C: [f64; 7] = [0.0, 1.0 / 5.0, 3.0 / 10.0, 4.0 / 5.0, 8.0 / 9.0, 1.0, 1.0];
+        #[rustfmt::skip]
+        const A: [[f64; 6]; 7] = [
+            [0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            [1.0 / 5.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            [3.0 / 40.0, 9.0 / 40.0, 0.0, 0.0, 0.0, 0.0],
+            [44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0, 0.0, 0.0, 0.0],
+            [19372.0 / 6561.0, -25360.0 / 2187.0, 64448.0 / 6561.0, -212.0 / 729.0, 0.0, 0.0],
+            [9017.0 / 3168.0, -355.0 / 33.0, 46732.0 / 5247.0, 49.0 / 176.0, -5103.0 / 18656.0, 0.0],
+            [35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0],
+        ];
+        const B5: [f64; 7] = [35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0, 0.0];
+        const B4: [f64; 7] = [
+            5179.0 / 57600.0, 0.0, 7571.0 / 16695.0, 393.0 / 640.0, -92097.0 / 339200.0, 187.0 / 2100.0, 1.0 / 40.0,
+        ];
+        const SAFETY: f64 = 0.9;
+        const MIN_SCALE: f64 = 0.2;
+        const MAX_SCALE: f64 = 5.0;
+        const MAX_REJECTIONS: usize = 25;
+
+        let rtol = self.rtol;
+        let atol = self.atol;
+        let dt_min = self.dt_min.max(1.0e-12);
+        let dt_max = self.dt_max.max(dt_min);
+
+        let mut attempts = 0usize;
+        loop {
+            let dt = self.dt.clamp(dt_min, dt_max);
+
+            // Stage 1 reuses the state already in self.x/self.v.
+            field.force_all(self.time, &self.x, &self.v, &mut self.fx);
+            for i in 0..n {
+                self.dp_kx[0][i] = self.v[i];
+                self.dp_kv[0][i] = (self.fx[i] - self.drag[i] * self.v[i]) / self.mass[i];
+            }
+
+            // Stages 2..=7.
+            for s in 1..7 {
+                for i in 0..n {
+                    let mut xi = self.x[i];
+                    let mut vi = self.v[i];
+                    for (j, kx) in self.dp_kx.iter().enumerate().take(s) {
+                        let a = A[s][j];
+                        xi += dt * a * kx[i];
+                        vi += dt * a * self.dp_kv[j][i];
+                    }
+                    self.dp_tmp_x[i] = xi;
+                    self.dp_tmp_v[i] = vi;
+                }
+                field.force_all(self.time + C[s] * dt, &self.dp_tmp_x, &self.dp_tmp_v, &mut self.fx);
+                for i in 0..n {
+                    self.dp_kx[s][i] = self.dp_tmp_v[i];
+                    self.dp_kv[s][i] = (self.fx[i] - self.drag[i] * self.dp_tmp_v[i]) / self.mass[i];
+                }
+            }
+
+            // 5th-order solution, 4th-order embedded solution, and the RMS
+            // error over every body and all 6 state components.
+            let mut err_sq_sum = 0.0;
+            for i in 0..n {
+                let mut dx5 = Vector3::new(0.0, 0.0, 0.0);
+                let mut dv5 = Vector3::new(0.0, 0.0, 0.0);
+                let mut dx4 = Vector3::new(0.0, 0.0, 0.0);
+                let mut dv4 = Vector3::new(0.0, 0.0, 0.0);
+                for s in 0..7 {
+                    dx5 += B5[s] * self.dp_kx[s][i];
+                    dv5 += B5[s] * self.dp_kv[s][i];
+                    dx4 += B4[s] * self.dp_kx[s][i];
+                    dv4 += B4[s] * self.dp_kv[s][i];
+                }
+                let x5 = self.x[i] + dt * dx5;
+                let v5 = self.v[i] + dt * dv5;
+                let x4 = self.x[i] + dt * dx4;
+                let v4 = self.v[i] + dt * dv4;
+                self.dp_next_x[i] = x5;
+                self.dp_next_v[i] = v5;
+
+                for k in 0..3 {
+                    let (a5, a4, cur) = (x5[k], x4[k], self.x[i][k]);
+                    let scale = atol + rtol * cur.abs().max(a5.abs());
+                    let e = (a5 - a4) / scale.max(1.0e-300);
+                    err_sq_sum += e * e;
+                }
+                for k in 0..3 {
+                    let (a5, a4, cur) = (v5[k], v4[k], self.v[i][k]);
+                    let scale = atol + rtol * cur.abs().max(a5.abs());
+                    let e = (a5 - a4) / scale.max(1.0e-300);
+                    err_sq_sum += e * e;
+                }
+            }
+            let err = (err_sq_sum / (n as f64 * 6.0)).sqrt();
+
+            let scale = if err > 0.0 {
+                (SAFETY * err.powf(-1.0 / 5.0)).clamp(MIN_SCALE, MAX_SCALE)
+            } else {
+                MAX_SCALE
+            };
+            let dt_new = (dt * scale).clamp(dt_min, dt_max);
+
+            if err <= 1.0 || attempts >= MAX_REJECTIONS {
+                for i in 0..n {
+                    self.x[i] = self.dp_next_x[i];
+                    self.v[i] = self.dp_next_v[i];
+                }
+                self.time += dt;
+                for i in 0..n {
+                    if let Some(t) = &mut self.traj[i] {
+                        t.push([
+                            self.x[i].x, self.x[i].y, self.x[i].z,
+                            self.v[i].x, self.v[i].y, self.v[i].z,
+                            self.time,
+                        ]);
+                    }
+                }
+                self.dt = dt_new;
+                if self.auto_resolve_collisions {
+                    self.resolve_collisions();
+                }
+                return dt;
+            }
+
+            self.dt = dt_new;
+            attempts += 1;
+        }
+    }
+
+    /// One velocity-Verlet (leapfrog) step: unlike RK4, this is symplectic,
+    /// so total energy on a conservative force field stays bounded over many
+    /// periods instead of slowly drifting. Computes `a_n`, advances position
+    /// with it, then evaluates `a_{n+1}` at the new position to average into
+    /// the velocity update. The drag term makes force velocity-dependent, so
+    /// the textbook `v_{n+1} = v_n + 0.5*(a_n+a_{n+1})*dt` would need an
+    /// implicit solve for `a_{n+1}`; instead this uses one fixed-point pass,
+    /// evaluating the new-position force against the explicit-Euler
+    /// predictor velocity `v_n + a_n*dt` rather than the still-unknown
+    /// `v_{n+1}`. Reuses the RK4 stage buffers (`k1v`/`k2v`/`tmp_x`/`tmp_v`)
+    /// instead of allocating new ones.
+    pub fn step_verlet_with_field<F: ForceField>(&mut self, field: &F) {
+        let n = self.len();
+        if n == 0 { return; }
+        let dt = self.dt;
+
+        // a_n
+        field.force_all(self.time, &self.x, &self.v, &mut self.fx);
+        for i in 0..n {
+            self.k1v[i] = (self.fx[i] - self.drag[i] * self.v[i]) / self.mass[i];
+        }
+
+        // x_{n+1} = x_n + v_n*dt + 0.5*a_n*dt^2
+        for i in 0..n {
+            self.tmp_x[i] = self.x[i] + self.v[i] * dt + 0.5 * self.k1v[i] * dt * dt;
+        }
+
+        // Fixed-point predictor velocity for the drag-dependent a_{n+1}.
+        for i in 0..n {
+            self.tmp_v[i] = self.v[i] + self.k1v[i] * dt;
+        }
+
+        // a_{n+1}
+        field.force_all(self.time + dt, &self.tmp_x, &self.tmp_v, &mut self.fx);
+        for i in 0..n {
+            self.k2v[i] = (self.fx[i] - self.drag[i] * self.tmp_v[i]) / self.mass[i];
+        }
+
+        // v_{n+1} = v_n + 0.5*(a_n + a_{n+1})*dt
+        for i in 0..n {
+            self.v[i] += 0.5 * (self.k1v[i] + self.k2v[i]) * dt;
+            self.x[i] = self.tmp_x[i];
+        }
+
+        self.time += dt;
+
+        for i in 0..n {
+            if let Some(t) = &mut self.traj[i] {
+                t.push([
+                    self.x[i].x, self.x[i].y, self.x[i].z,
+                    self.v[i].x, self.v[i].y, self.v[i].z,
+                    self.time,
+                ]);
+            }
+        }
+
+        if self.auto_resolve_collisions {
+            self.resolve_collisions();
+        }
+    }
+
+    /// One RK4 step of the rotational state: orientation `q` and angular
+    /// velocity `omega`, driven by `torque_field` and Euler's rigid-body
+    /// equation `I*dw/dt = tau - w x (I*w)` coupled with the quaternion
+    /// kinematics `dq/dt = 0.5*q(x)(0,w)`. Intermediate RK4 stages carry a
+    /// non-unit `q`; only the combined end-of-step orientation is
+    /// renormalized, to keep it on the unit sphere without biasing the
+    /// stage derivatives. Independent of the translational integrators
+    /// above — it reads `self.time` as the step's start time and does not
+    /// advance it itself, so call it alongside whichever `step*` method
+    /// advances time for the same `dt`.
+    pub fn step_rotational_with_field<T: TorqueField>(&mut self, torque_field: &T) {
+        let n = self.len();
+        if n == 0 { return; }
+        let dt = self.dt;
+
+        // k1
+        torque_field.torque_all(self.time, &self.q, &self.omega, &mut self.torques);
+        for i in 0..n {
+            let q0 = self.q[i].into_inner();
+            let (dq, dw) = rigid_body_derivative(q0, self.omega[i], self.inertia[i], self.torques[i]);
+            self.rq1[i] = dq;
+            self.rw1[i] = dw;
+            self.tmp_q[i] = q0 + scale_quat(dq, 0.5 * dt);
+            self.tmp_w[i] = self.omega[i] + 0.5 * dt * dw;
+            self.tmp_q_unit[i] = UnitQuaternion::new_normalize(self.tmp_q[i]);
+        }
+
+        // k2
+        torque_field.torque_all(self.time + 0.5 * dt, &self.tmp_q_unit, &self.tmp_w, &mut self.torques);
+        for i in 0..n {
+            let (dq, dw) = rigid_body_derivative(self.tmp_q[i], self.tmp_w[i], self.inertia[i], self.torques[i]);
+            self.rq2[i] = dq;
+            self.rw2[i] = dw;
+            let q0 = self.q[i].into_inner();
+            self.tmp_q[i] = q0 + scale_quat(dq, 0.5 * dt);
+            self.tmp_w[i] = self.omega[i] + 0.5 * dt * dw;
+            self.tmp_q_unit[i] = UnitQuaternion::new_normalize(self.tmp_q[i]);
+        }
+
+        // k3
+        torque_field.torque_all(self.time + 0.5 * dt, &self.tmp_q_unit, &self.tmp_w, &mut self.torques);
+        for i in 0..n {
+            let (dq, dw) = rigid_body_derivative(self.tmp_q[i], self.tmp_w[i], self.inertia[i], self.torques[i]);
+            self.rq3[i] = dq;
+            self.rw3[i] = dw;
+            let q0 = self.q[i].into_inner();
+            self.tmp_q[i] = q0 + scale_quat(dq, dt);
+            self.tmp_w[i] = self.omega[i] + dt * dw;
+            self.tmp_q_unit[i] = UnitQuaternion::new_normalize(self.tmp_q[i]);
+        }
+
+        // k4
+        torque_field.torque_all(self.time + dt, &self.tmp_q_unit, &self.tmp_w, &mut self.torques);
+        for i in 0..n {
+            let (dq, dw) = rigid_body_derivative(self.tmp_q[i], self.tmp_w[i], self.inertia[i], self.torques[i]);
+            self.rq4[i] = dq;
+            self.rw4[i] = dw;
+        }
+
+        // Обновление состояния; кватернион нормируется один раз, после
+        // полного шага.
+        for i in 0..n {
+            let q0 = self.q[i].into_inner();
+            let dq_sum = self.rq1[i] + scale_quat(self.rq2[i], 2.0) + scale_quat(self.rq3[i], 2.0) + self.rq4[i];
+            let q_next = q0 + scale_quat(dq_sum, dt / 6.0);
+            self.q[i] = UnitQuaternion::new_normalize(q_next);
+            self.omega[i] += dt * (self.rw1[i] + 2.0 * self.rw2[i] + 2.0 * self.rw3[i] + self.rw4[i]) / 6.0;
+        }
+    }
+
+    /// Параллельный шаг RK4 (только если включена фича parallel). Runs on the
+    /// pool set by `set_threads`, if any, otherwise on the ambient rayon pool.
     #[cfg(feature = "parallel")]
     pub fn step_par_with_field<F: ForceField>(&mut self, field: &F) {
+        match self.thread_pool.clone() {
+            Some(pool) => pool.install(|| self.step_par_with_field_inner(field)),
+            None => self.step_par_with_field_inner(field),
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    fn step_par_with_field_inner<F: ForceField>(&mut self, field: &F) {
         use rayon::prelude::*;
         let n = self.len();
         if n == 0 { return; }
@@ -386,5 +970,12 @@ impl Simulator {
                 ]);
             }
         });
+
+        // resolve_collisions() mutates self.x/self.v serially via a HashMap
+        // broad phase, so it runs outside the par_iter sections above rather
+        // than being parallelized itself.
+        if self.auto_resolve_collisions {
+            self.resolve_collisions();
+        }
     }
 }
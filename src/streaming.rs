@@ -0,0 +1,155 @@
+use crate::engine::Engine;
+use nalgebra::Vector3;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::JoinHandle;
+
+/// One committed tick of a streaming `Engine` run.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub tick_index: u64,
+    pub positions_flat: Vec<f32>,
+    pub groups: Vec<u32>,
+    pub attitudes_flat: Vec<f32>,
+}
+
+/// Control messages a running `StreamHandle` drains and applies between
+/// ticks, so none of them can land mid-integration.
+#[derive(Debug, Clone)]
+pub enum Control {
+    Pause,
+    Resume,
+    SetAlgorithm(String),
+    SetUniformForce(Vector3<f64>),
+    SetPosition(usize, Vector3<f64>),
+    Stop,
+}
+
+/// Handle to an `Engine` running on its own thread, fed by `run_async`.
+/// Frames arrive on `frames` as they're committed; queue a `Control`
+/// message with `send` to pause/resume the run or apply an update between
+/// ticks. Dropping the handle (or calling `stop`) stops the worker thread.
+pub struct StreamHandle {
+    pub frames: Receiver<Frame>,
+    control: Sender<Control>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl StreamHandle {
+    /// Queue a control message; applied before the worker's next tick.
+    pub fn send(&self, msg: Control) {
+        let _ = self.control.send(msg);
+    }
+
+    /// Signal the worker to stop and block until its thread exits.
+    pub fn stop(mut self) {
+        let _ = self.control.send(Control::Stop);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for StreamHandle {
+    fn drop(&mut self) {
+        let _ = self.control.send(Control::Stop);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Spawn `engine` onto its own thread and advance it one tick at a time,
+/// emitting a `Frame` on the returned handle's `frames` channel every
+/// `frame_stride` ticks (clamped to at least 1) until `steps` ticks have run
+/// (or forever, if `steps` is `None`). While paused, the worker blocks on
+/// its control channel instead of busy-waiting.
+pub fn run_async(mut engine: Engine, steps: Option<u64>, frame_stride: u64) -> StreamHandle {
+    let stride = frame_stride.max(1);
+    let (frame_tx, frame_rx) = mpsc::channel();
+    let (control_tx, control_rx) = mpsc::channel();
+
+    let worker = std::thread::spawn(move || {
+        let mut paused = false;
+        let mut tick_index: u64 = 0;
+        loop {
+            while let Ok(msg) = control_rx.try_recv() {
+                if apply_control(&mut engine, msg, &mut paused) {
+                    return;
+                }
+            }
+
+            if paused {
+                match control_rx.recv() {
+                    Ok(msg) => {
+                        if apply_control(&mut engine, msg, &mut paused) {
+                            return;
+                        }
+                    }
+                    Err(_) => return, // handle dropped while paused
+                }
+                continue;
+            }
+
+            if let Some(limit) = steps {
+                if tick_index >= limit {
+                    return;
+                }
+            }
+
+            engine.tick();
+            if tick_index % stride == 0 {
+                let frame = Frame {
+                    tick_index,
+                    positions_flat: engine.positions_flat(),
+                    groups: engine.groups(),
+                    attitudes_flat: engine.attitudes_flat(),
+                };
+                if frame_tx.send(frame).is_err() {
+                    return; // receiver dropped
+                }
+            }
+            tick_index += 1;
+        }
+    });
+
+    StreamHandle {
+        frames: frame_rx,
+        control: control_tx,
+        worker: Some(worker),
+    }
+}
+
+/// Apply one control message to `engine`. Returns `true` if the caller
+/// should stop its loop (`Control::Stop`).
+fn apply_control(engine: &mut Engine, msg: Control, paused: &mut bool) -> bool {
+    match msg {
+        Control::Pause => *paused = true,
+        Control::Resume => *paused = false,
+        Control::SetAlgorithm(id) => {
+            let _ = engine.set_algorithm(&id);
+        }
+        Control::SetUniformForce(f) => engine.set_uniform_force(f),
+        Control::SetPosition(i, pos) => engine.set_position(i, pos),
+        Control::Stop => return true,
+    }
+    false
+}
+
+/// Blocking counterpart to `run_async`: apply any `pending` control
+/// messages (in order), advance `engine` exactly one tick, and return the
+/// committed frame tagged with `tick_index`. For a caller that wants to own
+/// the integration loop itself but still drive it one confirmed tick at a
+/// time.
+pub fn step_and_confirm(engine: &mut Engine, tick_index: u64, pending: &mut Vec<Control>) -> Frame {
+    let mut paused = false;
+    for msg in pending.drain(..) {
+        apply_control(engine, msg, &mut paused);
+    }
+    engine.tick();
+    Frame {
+        tick_index,
+        positions_flat: engine.positions_flat(),
+        groups: engine.groups(),
+        attitudes_flat: engine.attitudes_flat(),
+    }
+}
@@ -3,12 +3,15 @@
 use crate::algorithms::flocking::FlockParams;
 use crate::algorithms::flocking_alpha::FlockAlphaParams;
 use crate::algorithms::formation_ecbf::FormationEcbfParams;
+use crate::algorithms::gravity::GravityParams;
+use crate::algorithms::noise::layered_noise3;
 use crate::algorithms::safe_flocking_alpha::SafeFlockAlphaParams;
 use crate::engine::{
-    algorithm_catalog, model_catalog, AlgorithmInfo, Engine, ModelInfo, ALGO_FLOCKING, MODEL_RING,
+    algorithm_catalog, model_catalog, AlgorithmInfo, Engine, GroupStats, ModelInfo, ALGO_FLOCKING,
+    MODEL_RING,
 };
 use nalgebra::Vector3;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
@@ -62,6 +65,12 @@ pub fn safe_flocking_alpha_defaults() -> JsValue {
     serde_wasm_bindgen::to_value(&params).unwrap_or(JsValue::NULL)
 }
 
+#[wasm_bindgen]
+pub fn gravity_defaults() -> JsValue {
+    let params = GravityParams::default();
+    serde_wasm_bindgen::to_value(&params).unwrap_or(JsValue::NULL)
+}
+
 fn model_info_to_js(info: &ModelInfo) -> JsValue {
     let obj = js_sys::Object::new();
     let _ = js_sys::Reflect::set(&obj, &JsValue::from_str("id"), &JsValue::from_str(info.id));
@@ -214,12 +223,41 @@ impl WasmSim {
             .map_err(|e| JsValue::from_str(&e))
     }
 
+    pub fn set_gravity_params(&mut self, params: JsValue) -> Result<(), JsValue> {
+        let params: GravityParams = serde_wasm_bindgen::from_value(params)
+            .map_err(|e| JsValue::from_str(&format!("invalid gravity params: {}", e)))?;
+        self.engine
+            .set_gravity_params(params)
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
     pub fn positions(&self) -> Vec<f32> { self.engine.positions_flat() }
 
     pub fn states(&self) -> Vec<f32> { self.engine.state_matrix_flat() }
 
     pub fn debug_states(&self) -> Vec<f32> { self.engine.debug_states_flat() }
 
+    /// Fill a caller-owned `out` buffer with the same layout as `positions`
+    /// and return how many floats were written, instead of allocating a
+    /// fresh array every call — lets a render loop reuse one
+    /// `Float32Array`/wasm-memory view across frames.
+    #[wasm_bindgen(js_name = "writePositions")]
+    pub fn write_positions(&self, out: &mut [f32]) -> Result<usize, JsValue> {
+        self.engine.write_positions(out).map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Zero-allocation counterpart to `states`.
+    #[wasm_bindgen(js_name = "writeStates")]
+    pub fn write_states(&self, out: &mut [f32]) -> Result<usize, JsValue> {
+        self.engine.write_state_matrix(out).map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Zero-allocation counterpart to `debug_states`.
+    #[wasm_bindgen(js_name = "writeDebugStates")]
+    pub fn write_debug_states(&self, out: &mut [f32]) -> Result<usize, JsValue> {
+        self.engine.write_debug_states(out).map_err(|e| JsValue::from_str(&e))
+    }
+
     pub fn dt(&self) -> f64 { self.engine.dt() }
 
     pub fn set_algorithm(&mut self, algorithm_id: &str) -> Result<(), JsValue> {
@@ -231,6 +269,136 @@ impl WasmSim {
     pub fn groups(&self) -> Vec<u32> { self.engine.groups() }
 
     pub fn attitudes(&self) -> Vec<f32> { self.engine.attitudes_flat() }
+
+    /// Zero-allocation counterpart to `attitudes`.
+    #[wasm_bindgen(js_name = "writeAttitudes")]
+    pub fn write_attitudes(&self, out: &mut [f32]) -> Result<usize, JsValue> {
+        self.engine.write_attitudes(out).map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Per-group aggregate stats for the current tick: one object per
+    /// distinct group id, `{ id, count, centroid, meanVelocity,
+    /// boundingMin, boundingMax, meanSpeed, spread }`.
+    #[wasm_bindgen(js_name = "groupStats")]
+    pub fn group_stats(&self) -> js_sys::Array {
+        let out = js_sys::Array::new();
+        for stats in self.engine.group_stats() {
+            out.push(&group_stats_to_js(&stats));
+        }
+        out
+    }
+
+    /// Serialize the full simulation (dt, plane2d, active algorithm, every
+    /// agent's state/mass/drag/group, and the active algorithm's params) to
+    /// a plain JS object suitable for `JSON.stringify`/later `restore`.
+    pub fn snapshot(&self) -> JsValue {
+        let agents = self
+            .engine
+            .body_snapshots()
+            .into_iter()
+            .map(|b| SnapshotAgent {
+                position: [b.state[0], b.state[1], b.state[2]],
+                velocity: [b.state[3], b.state[4], b.state[5]],
+                mass: b.mass,
+                drag: b.drag_coefficient,
+                group: b.group,
+            })
+            .collect();
+        let snap = SimSnapshot {
+            dt: self.engine.dt(),
+            plane2d: self.engine.plane_2d(),
+            algorithm: self.engine.algorithm_id().to_string(),
+            agents,
+            flock_params: self.engine.flock_params(),
+            flock_alpha_params: self.engine.flock_alpha_params(),
+            formation_ecbf_params: self.engine.formation_ecbf_params(),
+            safe_flocking_alpha_params: self.engine.safe_flocking_alpha_params(),
+        };
+        serde_wasm_bindgen::to_value(&snap).unwrap_or(JsValue::NULL)
+    }
+
+    /// Rebuild this simulation in place from a `snapshot()` payload: builds
+    /// a fresh `Engine` via the same `new_custom` path `newFromConfig` uses,
+    /// then re-applies whichever algorithm params were captured.
+    pub fn restore(&mut self, snapshot: JsValue) -> Result<(), JsValue> {
+        let snap: SimSnapshot = serde_wasm_bindgen::from_value(snapshot)
+            .map_err(|e| JsValue::from_str(&format!("invalid snapshot: {}", e)))?;
+        let configs: Vec<crate::BodyConfig> = snap
+            .agents
+            .iter()
+            .map(|a| crate::BodyConfig {
+                mass: a.mass,
+                state: [
+                    a.position[0], a.position[1], a.position[2],
+                    a.velocity[0], a.velocity[1], a.velocity[2],
+                ],
+                drag_coefficient: a.drag,
+                trajectory_write: false,
+                group: a.group,
+                orientation: crate::sim::IDENTITY_ORIENTATION,
+                angular_velocity: [0.0, 0.0, 0.0],
+                inertia: crate::sim::UNIT_INERTIA,
+                radius: crate::sim::DEFAULT_RADIUS,
+            })
+            .collect();
+        let mut engine = Engine::new_custom(configs, snap.dt, Some(&snap.algorithm), snap.plane2d)
+            .map_err(|e| JsValue::from_str(&e))?;
+        if let Some(p) = snap.flock_params {
+            let _ = engine.set_flock_params(p);
+        }
+        if let Some(p) = snap.flock_alpha_params {
+            let _ = engine.set_flock_alpha_params(p);
+        }
+        if let Some(p) = snap.formation_ecbf_params {
+            let _ = engine.set_formation_ecbf_params(p);
+        }
+        if let Some(p) = snap.safe_flocking_alpha_params {
+            let _ = engine.set_safe_flocking_alpha_params(p);
+        }
+        self.engine = engine;
+        Ok(())
+    }
+}
+
+fn vec3_to_js(v: [f64; 3]) -> JsValue {
+    let arr = js_sys::Array::new();
+    arr.push(&JsValue::from_f64(v[0]));
+    arr.push(&JsValue::from_f64(v[1]));
+    arr.push(&JsValue::from_f64(v[2]));
+    JsValue::from(arr)
+}
+
+fn group_stats_to_js(stats: &GroupStats) -> JsValue {
+    let obj = js_sys::Object::new();
+    let _ = js_sys::Reflect::set(&obj, &JsValue::from_str("id"), &JsValue::from_f64(stats.id as f64));
+    let _ = js_sys::Reflect::set(
+        &obj,
+        &JsValue::from_str("count"),
+        &JsValue::from_f64(stats.count as f64),
+    );
+    let _ = js_sys::Reflect::set(&obj, &JsValue::from_str("centroid"), &vec3_to_js(stats.centroid));
+    let _ = js_sys::Reflect::set(
+        &obj,
+        &JsValue::from_str("meanVelocity"),
+        &vec3_to_js(stats.mean_velocity),
+    );
+    let _ = js_sys::Reflect::set(
+        &obj,
+        &JsValue::from_str("boundingMin"),
+        &vec3_to_js(stats.bounding_min),
+    );
+    let _ = js_sys::Reflect::set(
+        &obj,
+        &JsValue::from_str("boundingMax"),
+        &vec3_to_js(stats.bounding_max),
+    );
+    let _ = js_sys::Reflect::set(
+        &obj,
+        &JsValue::from_str("meanSpeed"),
+        &JsValue::from_f64(stats.mean_speed),
+    );
+    let _ = js_sys::Reflect::set(&obj, &JsValue::from_str("spread"), &JsValue::from_f64(stats.spread));
+    JsValue::from(obj)
 }
 
 #[derive(Debug, Deserialize)]
@@ -276,10 +444,51 @@ struct CustomCluster {
     group: Option<usize>,
     #[serde(default)]
     mass: Option<f64>,
+    #[serde(default)]
+    seed: Option<u64>,
+    #[serde(default)]
+    octaves: Option<u32>,
+    #[serde(default)]
+    persistence: Option<f64>,
+    #[serde(default)]
+    lacunarity: Option<f64>,
+}
+
+/// One agent's persisted state inside a `SimSnapshot`.
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotAgent {
+    position: [f64; 3],
+    velocity: [f64; 3],
+    mass: f64,
+    drag: f64,
+    group: usize,
+}
+
+/// Full save/load payload for `WasmSim::snapshot`/`WasmSim::restore`: dt,
+/// plane-2d flag, active algorithm id, every agent's state, and whichever
+/// one of the four algorithm param sets is active (the rest are `None`).
+#[derive(Debug, Serialize, Deserialize)]
+struct SimSnapshot {
+    dt: f64,
+    plane2d: bool,
+    algorithm: String,
+    agents: Vec<SnapshotAgent>,
+    #[serde(default)]
+    flock_params: Option<FlockParams>,
+    #[serde(default)]
+    flock_alpha_params: Option<FlockAlphaParams>,
+    #[serde(default)]
+    formation_ecbf_params: Option<FormationEcbfParams>,
+    #[serde(default)]
+    safe_flocking_alpha_params: Option<SafeFlockAlphaParams>,
 }
 
 fn default_shape() -> String { "sphere".to_string() }
 
+const DEFAULT_NOISE_OCTAVES: u32 = 4;
+const DEFAULT_NOISE_PERSISTENCE: f64 = 0.5;
+const DEFAULT_NOISE_LACUNARITY: f64 = 2.0;
+
 fn build_custom_configs(cfg: CustomConfig) -> Result<(Vec<crate::BodyConfig>, f64, bool, Option<String>), JsValue> {
     let dt = cfg.dt.unwrap_or(crate::models::particles::DEMO_DT);
     let plane_2d = cfg.plane2d.unwrap_or(false);
@@ -300,6 +509,10 @@ fn build_custom_configs(cfg: CustomConfig) -> Result<(Vec<crate::BodyConfig>, f6
                 drag_coefficient: a.drag.unwrap_or(0.0),
                 trajectory_write: false,
                 group: a.group.unwrap_or(0),
+                orientation: crate::sim::IDENTITY_ORIENTATION,
+                angular_velocity: [0.0, 0.0, 0.0],
+                inertia: crate::sim::UNIT_INERTIA,
+                radius: crate::sim::DEFAULT_RADIUS,
             });
         }
     }
@@ -318,33 +531,89 @@ fn build_cluster(c: &CustomCluster) -> Result<Vec<crate::BodyConfig>, JsValue> {
     match c.shape.as_str() {
         "sphere" | "ball" => build_sphere_cluster(c),
         "circle" | "ring" => build_circle_cluster(c),
+        "noise_shell" | "asteroid" => build_noise_shell_cluster(c),
         other => Err(JsValue::from_str(&format!("unknown cluster shape '{}'", other))),
     }
 }
 
-fn build_sphere_cluster(c: &CustomCluster) -> Result<Vec<crate::BodyConfig>, JsValue> {
-    let count = c.count;
-    if count == 0 {
-        return Ok(Vec::new());
-    }
-    let mut configs = Vec::with_capacity(count);
-    // Fibonacci sphere distribution for deterministic spread.
+/// Fibonacci sphere distribution of `count` unit directions, deterministic
+/// and evenly spread regardless of `count`. Shared by `build_sphere_cluster`
+/// and `build_noise_shell_cluster`.
+fn fibonacci_directions(count: usize) -> Vec<Vector3<f64>> {
     let golden = (1.0 + 5.0_f64.sqrt()) * 0.5;
     let ga = 2.0 - 1.0 / golden;
+    let mut dirs = Vec::with_capacity(count);
     for i in 0..count {
         let fi = i as f64 + 0.5;
         let z = 1.0 - (2.0 * fi) / count as f64;
         let r = (1.0 - z * z).max(0.0).sqrt();
         let theta = 2.0 * std::f64::consts::PI * fi * ga;
-        let x = theta.cos() * r;
-        let y = theta.sin() * r;
-        let pos = Vector3::new(x, y, z) * c.radius + Vector3::new(c.center[0], c.center[1], c.center[2]);
+        dirs.push(Vector3::new(theta.cos() * r, theta.sin() * r, z));
+    }
+    dirs
+}
+
+fn build_sphere_cluster(c: &CustomCluster) -> Result<Vec<crate::BodyConfig>, JsValue> {
+    let count = c.count;
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+    let center = Vector3::new(c.center[0], c.center[1], c.center[2]);
+    let mut configs = Vec::with_capacity(count);
+    for dir in fibonacci_directions(count) {
+        let pos = dir * c.radius + center;
 
         let base_vel = c.velocity.unwrap_or([0.0, 0.0, 0.0]);
         let mut vel = Vector3::new(base_vel[0], base_vel[1], base_vel[2]);
         if let Some(radial) = c.radial_speed {
-            let dir = Vector3::new(x, y, z).normalize();
-            vel += dir * radial;
+            vel += dir.normalize() * radial;
+        }
+
+        configs.push(crate::BodyConfig {
+            mass: c.mass.unwrap_or(1.0),
+            state: [pos.x, pos.y, pos.z, vel.x, vel.y, vel.z],
+            drag_coefficient: c.drag.unwrap_or(0.0),
+            trajectory_write: false,
+            group: c.group.unwrap_or(0),
+            orientation: crate::sim::IDENTITY_ORIENTATION,
+            angular_velocity: [0.0, 0.0, 0.0],
+            inertia: crate::sim::UNIT_INERTIA,
+            radius: crate::sim::DEFAULT_RADIUS,
+        });
+    }
+    Ok(configs)
+}
+
+/// Like `build_sphere_cluster`, but perturbs each direction's radius with
+/// layered simplex/Perlin-style noise so the spawned body looks like a
+/// lumpy asteroid instead of a perfect sphere:
+/// `r(d) = radius * (1 + Σ_k amp_k * noise3(d * freq_k))`, with
+/// `freq_0 = 1, amp_0 = 1`, `freq_{k+1} = lacunarity * freq_k`,
+/// `amp_{k+1} = persistence * amp_k`. Radial velocity still uses the
+/// original unit direction, matching `build_sphere_cluster`.
+fn build_noise_shell_cluster(c: &CustomCluster) -> Result<Vec<crate::BodyConfig>, JsValue> {
+    let count = c.count;
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+    let seed = c.seed.unwrap_or(0);
+    let octaves = c.octaves.unwrap_or(DEFAULT_NOISE_OCTAVES);
+    let persistence = c.persistence.unwrap_or(DEFAULT_NOISE_PERSISTENCE);
+    let lacunarity = c.lacunarity.unwrap_or(DEFAULT_NOISE_LACUNARITY);
+
+    let center = Vector3::new(c.center[0], c.center[1], c.center[2]);
+    let mut configs = Vec::with_capacity(count);
+    for dir in fibonacci_directions(count) {
+        let shell = layered_noise3(dir, seed, octaves, persistence, lacunarity);
+        // Guard against the noise sum pushing the radius through zero; a
+        // surface this dented isn't useful as a swarm shape anyway.
+        let radius = (c.radius * (1.0 + shell)).max(c.radius * 0.05);
+        let pos = dir * radius + center;
+
+        let base_vel = c.velocity.unwrap_or([0.0, 0.0, 0.0]);
+        let mut vel = Vector3::new(base_vel[0], base_vel[1], base_vel[2]);
+        if let Some(radial) = c.radial_speed {
+            vel += dir.normalize() * radial;
         }
 
         configs.push(crate::BodyConfig {
@@ -353,6 +622,10 @@ fn build_sphere_cluster(c: &CustomCluster) -> Result<Vec<crate::BodyConfig>, JsV
             drag_coefficient: c.drag.unwrap_or(0.0),
             trajectory_write: false,
             group: c.group.unwrap_or(0),
+            orientation: crate::sim::IDENTITY_ORIENTATION,
+            angular_velocity: [0.0, 0.0, 0.0],
+            inertia: crate::sim::UNIT_INERTIA,
+            radius: crate::sim::DEFAULT_RADIUS,
         });
     }
     Ok(configs)
@@ -382,6 +655,10 @@ fn build_circle_cluster(c: &CustomCluster) -> Result<Vec<crate::BodyConfig>, JsV
             drag_coefficient: c.drag.unwrap_or(0.0),
             trajectory_write: false,
             group: c.group.unwrap_or(0),
+            orientation: crate::sim::IDENTITY_ORIENTATION,
+            angular_velocity: [0.0, 0.0, 0.0],
+            inertia: crate::sim::UNIT_INERTIA,
+            radius: crate::sim::DEFAULT_RADIUS,
         });
     }
     Ok(configs)